@@ -0,0 +1,167 @@
+//! Derive macros for `geneos-toolkit`.
+//!
+//! Use these through the `derive` feature of `geneos-toolkit`, which
+//! re-exports them next to the traits they implement.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, Ident, LitStr, parse_macro_input};
+
+/// Derives `geneos_toolkit::dataview::ToRow` for a struct with named fields.
+///
+/// The row name comes from the field marked `#[torow(name)]`, or from the
+/// first field that is not skipped if none is marked. Every other field
+/// becomes a cell, in declaration order, with the field name as its column.
+/// Field types must implement `Display`; generic structs need the matching
+/// bounds.
+///
+/// Field attributes:
+/// - `#[torow(name)]`: use this field as the row name.
+/// - `#[torow(skip)]`: leave this field out of the row.
+/// - `#[torow(rename = "column")]`: use `column` instead of the field name.
+///   Not allowed on the row name field, which has no column.
+#[proc_macro_derive(ToRow, attributes(torow))]
+pub fn derive_to_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_to_row(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+struct Field {
+    ident: Ident,
+    rename: Option<LitStr>,
+    skip: bool,
+    name: Option<Span>,
+}
+
+impl Field {
+    fn column(&self) -> String {
+        match &self.rename {
+            Some(rename) => rename.value(),
+            None => self.ident.unraw().to_string(),
+        }
+    }
+}
+
+fn expand_to_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let data = match &input.data {
+        Data::Struct(data) => data,
+        Data::Enum(data) => {
+            return Err(Error::new(
+                data.enum_token.span,
+                "ToRow can only be derived for structs",
+            ));
+        }
+        Data::Union(data) => {
+            return Err(Error::new(
+                data.union_token.span,
+                "ToRow can only be derived for structs",
+            ));
+        }
+    };
+    let named = match &data.fields {
+        Fields::Named(named) => named,
+        fields => {
+            return Err(Error::new(
+                fields.span(),
+                "ToRow requires a struct with named fields",
+            ));
+        }
+    };
+
+    let fields = named
+        .named
+        .iter()
+        .map(parse_field)
+        .collect::<syn::Result<Vec<_>>>()?;
+
+    let mut marked = fields.iter().filter_map(|field| field.name);
+    let name_index = match (marked.next(), marked.next()) {
+        (Some(_), Some(second)) => {
+            return Err(Error::new(second, "only one field can be #[torow(name)]"));
+        }
+        (Some(_), None) => fields.iter().position(|field| field.name.is_some()),
+        (None, _) => fields.iter().position(|field| !field.skip),
+    };
+    let name_index = name_index.ok_or_else(|| {
+        Error::new(
+            input.ident.span(),
+            "ToRow requires at least one field for the row name",
+        )
+    })?;
+    let name_field = &fields[name_index];
+    if let Some(rename) = &name_field.rename {
+        return Err(Error::new(
+            rename.span(),
+            "#[torow(rename)] has no effect on the row name field",
+        ));
+    }
+
+    let name_ident = &name_field.ident;
+    let cells = fields
+        .iter()
+        .enumerate()
+        .filter(|(index, field)| *index != name_index && !field.skip)
+        .map(|(_, field)| {
+            let ident = &field.ident;
+            let column = field.column();
+            quote!(.add_cell(#column, &self.#ident))
+        });
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::geneos_toolkit::dataview::ToRow for #ident #ty_generics #where_clause {
+            fn to_row(&self) -> ::geneos_toolkit::dataview::Row {
+                ::geneos_toolkit::dataview::Row::new(&self.#name_ident)
+                    #(#cells)*
+            }
+        }
+    })
+}
+
+/// Reads a field and its `#[torow(...)]` options, ignoring other attributes.
+fn parse_field(field: &syn::Field) -> syn::Result<Field> {
+    let mut parsed = Field {
+        ident: field.ident.clone().expect("named fields have an ident"),
+        rename: None,
+        skip: false,
+        name: None,
+    };
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("torow"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("name") {
+                parsed.name = Some(meta.path.span());
+            } else if meta.path.is_ident("rename") {
+                let column: LitStr = meta.value()?.parse()?;
+                if column.value().is_empty() {
+                    return Err(Error::new(
+                        column.span(),
+                        "#[torow(rename = ...)] needs a non-empty column name",
+                    ));
+                }
+                parsed.rename = Some(column);
+            } else {
+                return Err(meta.error("unknown torow option, expected `name`, `skip` or `rename`"));
+            }
+            Ok(())
+        })?;
+    }
+    if let (Some(name), true) = (parsed.name, parsed.skip) {
+        return Err(Error::new(
+            name,
+            "the #[torow(name)] field cannot be skipped",
+        ));
+    }
+    Ok(parsed)
+}
@@ -1,12 +1,45 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
 use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::severity::Severity;
 
 #[derive(Debug)]
 pub enum DataviewError {
     MissingRowHeader,
     MissingValue,
-    EmptyName(String),
+    MissingHeadline,
+    /// A name of the given `kind` (`row`, `column`, `headline`,
+    /// `row header` or `row header alias`) is empty. `context` names where
+    /// it was found, e.g. the row holding a cell in an empty column, when
+    /// that is known.
+    EmptyName {
+        kind: String,
+        context: Option<String>,
+    },
+    FieldCountMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    DuplicateColumn(String, String),
+    RowHeaderCollision(String),
+    HeadlinePrefixInKey(String),
+    /// A column written at build time, such as the one set with
+    /// [`DataviewBuilder::style_rows`], already holds values.
+    ColumnExists(String),
+    /// A user headline uses a key the library writes itself, such as
+    /// [`DELTA_MARKER`].
+    ReservedHeadline(String),
+    /// The rendered view is `bytes` long, over the `limit` set with
+    /// [`DataviewBuilder::max_bytes`].
+    TooLarge {
+        bytes: usize,
+        limit: usize,
+    },
 }
 
 impl fmt::Display for DataviewError {
@@ -14,13 +47,63 @@ impl fmt::Display for DataviewError {
         match self {
             DataviewError::MissingRowHeader => write!(f, "The Dataview must have a row header"),
             DataviewError::MissingValue => write!(f, "The Dataview must have at least one value"),
-            DataviewError::EmptyName(field) => write!(f, "Empty {field} name is not allowed"),
+            DataviewError::MissingHeadline => {
+                write!(f, "The Dataview must have at least one headline")
+            }
+            DataviewError::EmptyName { kind, context } => {
+                write!(f, "Empty {kind} name is not allowed")?;
+                match context {
+                    Some(context) => write!(f, " ({context})"),
+                    None => Ok(()),
+                }
+            }
+            DataviewError::FieldCountMismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Rendered line {line} has {found} fields, expected {expected}"
+            ),
+            DataviewError::DuplicateColumn(first, second) => {
+                write!(f, "Columns '{first}' and '{second}' differ only by case")
+            }
+            DataviewError::RowHeaderCollision(header) => {
+                write!(f, "Row header '{header}' is also a column name")
+            }
+            DataviewError::HeadlinePrefixInKey(key) => {
+                write!(
+                    f,
+                    "Headline key '{key}' starts with the headline marker '<!>'"
+                )
+            }
+            DataviewError::ColumnExists(column) => {
+                write!(f, "Column '{column}' already exists")
+            }
+            DataviewError::ReservedHeadline(key) => {
+                write!(f, "Headline key '{key}' is reserved")
+            }
+            DataviewError::TooLarge { bytes, limit } => {
+                write!(
+                    f,
+                    "Rendered Dataview is {bytes} bytes, over the {limit} byte limit"
+                )
+            }
         }
     }
 }
 
 impl Error for DataviewError {}
 
+impl DataviewError {
+    fn empty_name(kind: &str, context: Option<String>) -> Self {
+        DataviewError::EmptyName {
+            kind: kind.to_string(),
+            context,
+        }
+    }
+}
+
 /// A Geneos Dataview object.
 ///
 /// This struct represents a Dataview, which is a structured representation of data
@@ -56,6 +139,10 @@ pub struct Dataview {
     values: HashMap<(String, String), String>,
     column_order: Vec<String>,
     row_order: Vec<String>,
+    column_defaults: HashMap<String, String>,
+    render_options: RenderOptions,
+    metadata: BTreeMap<String, String>,
+    sanitizer: Sanitizer,
 }
 
 impl Dataview {
@@ -85,11 +172,38 @@ impl Dataview {
         &self.headline_order
     }
 
+    /// Returns the metadata added with [`DataviewBuilder::add_metadata`],
+    /// sorted by key. Metadata is never rendered.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
     /// Returns a cell value for the given row/column, if present.
     pub fn value(&self, row: &str, column: &str) -> Option<&String> {
         self.values.get(&(row.to_string(), column.to_string()))
     }
 
+    /// Returns an owned copy of the cell value for the given row/column, if
+    /// present.
+    ///
+    /// Like [`Dataview::value`], this is the raw value as it was added:
+    /// escaping is only applied when the view is rendered, so delimiters and
+    /// backslashes come back exactly as stored.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("path")
+    ///     .add_value("tmp", "note", "a,b")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.value_owned("tmp", "note"), Some("a,b".to_string()));
+    /// ```
+    pub fn value_owned(&self, row: &str, column: &str) -> Option<String> {
+        self.value(row, column).cloned()
+    }
+
     /// Returns the column names in display order.
     pub fn column_order(&self) -> &[String] {
         &self.column_order
@@ -99,1158 +213,7132 @@ impl Dataview {
     pub fn row_order(&self) -> &[String] {
         &self.row_order
     }
-}
-
-/// Strips Unicode control characters (categories Cc and Cf) except ASCII
-/// whitespace (tab, newline, carriage return, space). Newlines and carriage
-/// returns are subsequently escaped by `escape_nasty_chars`.
-fn strip_unicode_controls(s: &str) -> String {
-    s.chars()
-        .filter(|&c| {
-            if c == '\t' || c == '\n' || c == '\r' || c == ' ' {
-                return true;
-            }
-            !c.is_control() && !is_unicode_format_char(c)
-        })
-        .collect()
-}
 
-/// Returns `true` for Unicode Cf (format) characters — RTL override, zero-width
-/// space, BOM, etc. Rust's `char::is_control()` covers Cc; this covers Cf.
-fn is_unicode_format_char(c: char) -> bool {
-    matches!(c as u32,
-        0x00AD              // SOFT HYPHEN
-        | 0x0600..=0x0605   // Arabic format chars
-        | 0x061C            // ARABIC LETTER MARK
-        | 0x06DD            // ARABIC END OF AYAH
-        | 0x070F            // SYRIAC ABBREVIATION MARK
-        | 0x08E2            // ARABIC DISPUTED END OF AYAH
-        | 0x180E            // MONGOLIAN VOWEL SEPARATOR
-        | 0x200B..=0x200F   // Zero-width space, joiners, LTR/RTL marks
-        | 0x202A..=0x202E   // Directional formatting
-        | 0x2060..=0x2064   // Word joiner, invisible operators
-        | 0x2066..=0x206F   // Directional isolates, deprecated chars
-        | 0xFEFF            // BOM / ZWNBSP
-        | 0xFFF9..=0xFFFB   // Interlinear annotations
-        | 0x110BD           // KAITHI NUMBER SIGN
-        | 0x110CD           // KAITHI NUMBER SIGN ABOVE
-        | 0x13430..=0x13438 // Egyptian hieroglyph format
-        | 0x1BCA0..=0x1BCA3 // Shorthand format controls
-        | 0x1D173..=0x1D17A // Musical symbol format
-        | 0xE0001           // LANGUAGE TAG
-        | 0xE0020..=0xE007F // TAG characters
-    )
-}
+    /// Renders with default options, ignoring the view's own render options.
+    fn render_plain(&self) -> String {
+        RenderView {
+            view: self,
+            options: &RenderOptions::default(),
+            removed: &[],
+        }
+        .to_string()
+    }
 
-trait GeneosEscaping {
-    fn escape_nasty_chars(&self) -> String;
-}
+    /// Returns the worst severity among the cells of `row`.
+    ///
+    /// Cell values are parsed case-insensitively as severity names
+    /// (`OK`, `WARNING`, `CRITICAL`, `UNDEFINED`); other values are ignored.
+    /// Returns `None` if the row does not exist or no cell holds a severity.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("check")
+    ///     .add_value("disk", "space", "OK")
+    ///     .add_value("disk", "inodes", "CRITICAL")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.row_max_severity("disk"), Some(Severity::Critical));
+    /// ```
+    pub fn row_max_severity(&self, row: &str) -> Option<Severity> {
+        self.column_order
+            .iter()
+            .filter_map(|col| self.value(row, col))
+            .filter_map(|value| value.parse::<Severity>().ok())
+            .max()
+    }
 
-impl GeneosEscaping for str {
-    fn escape_nasty_chars(&self) -> String {
-        let mut output = String::with_capacity(self.len());
+    /// Returns `true` if any cell of `row` holds a number greater than
+    /// `threshold`.
+    ///
+    /// Cells are parsed leniently: surrounding whitespace and a trailing unit
+    /// such as `%`, `MB` or ` ms` are ignored. Cells that still do not parse
+    /// are skipped. Returns `false` if the row does not exist.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_value("sda", "used", "97%")
+    ///     .add_value("sda", "mount", "/")
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(view.row_exceeds("sda", 90.0));
+    /// assert!(!view.row_exceeds("sda", 99.0));
+    /// ```
+    pub fn row_exceeds(&self, row: &str, threshold: f64) -> bool {
+        self.column_order
+            .iter()
+            .filter_map(|col| self.value(row, col))
+            .filter_map(|value| parse_with_unit(value))
+            .any(|number| number > threshold)
+    }
 
-        // C1: Escape <!> at string start to prevent headline injection
-        let s = if let Some(rest) = self.strip_prefix("<!>") {
-            output.push_str("\\<!>");
-            rest
+    /// Checks that every present cell in `column` is numeric, before sending
+    /// a column that gateway rules treat as a number.
+    ///
+    /// Cells are parsed leniently, like [`Dataview::row_exceeds`]: whitespace
+    /// and a trailing unit such as `%` or `MB` are ignored. Missing cells are
+    /// skipped, so a column that does not exist is `Ok`. Otherwise returns
+    /// every `(row, value)` that does not parse, in row order.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_value("sda", "used", "97%")
+    ///     .add_value("sdb", "used", "N/A")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     view.validate_numeric_column("used"),
+    ///     Err(vec![("sdb".to_string(), "N/A".to_string())])
+    /// );
+    /// ```
+    pub fn validate_numeric_column(&self, column: &str) -> Result<(), Vec<(String, String)>> {
+        let invalid: Vec<(String, String)> = self
+            .row_order
+            .iter()
+            .filter_map(|row| Some((row, self.value(row, column)?)))
+            .filter(|(_, value)| parse_with_unit(value).is_none())
+            .map(|(row, value)| (row.clone(), value.clone()))
+            .collect();
+        if invalid.is_empty() {
+            Ok(())
         } else {
-            self
-        };
-
-        for c in s.chars() {
-            match c {
-                '\\' => output.push_str("\\\\"),
-                ',' => output.push_str("\\,"),
-                '\n' => output.push_str("\\n"),
-                '\r' => output.push_str("\\r"),
-                '\0' => output.push_str("\\0"),
-                c => output.push(c),
-            }
+            Err(invalid)
         }
-        output
     }
-}
 
-fn write_header_row(
-    f: &mut fmt::Formatter<'_>,
-    row_header: &str,
-    columns: &[String],
-) -> fmt::Result {
-    write!(f, "{}", row_header.escape_nasty_chars())?;
-    for col in columns {
-        write!(f, ",{}", col.escape_nasty_chars())?;
+    /// Returns the columns, in display order, where every present cell is a
+    /// number, e.g. to pick the columns worth charting.
+    ///
+    /// Cells are parsed like [`Dataview::validate_numeric_column`]: leading
+    /// and trailing whitespace is ignored, and so is a trailing unit made of
+    /// letters and `%`, such as `%`, `MB` or ` ms`. Columns with no present
+    /// cells are not included.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_value("sda", "used", "97%")
+    ///     .add_value("sda", "mount", "/")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.numeric_columns(), vec!["used"]);
+    /// ```
+    pub fn numeric_columns(&self) -> Vec<&str> {
+        self.column_order
+            .iter()
+            .filter(|col| {
+                self.row_order
+                    .iter()
+                    .any(|row| self.value(row, col).is_some())
+            })
+            .filter(|col| self.validate_numeric_column(col).is_ok())
+            .map(String::as_str)
+            .collect()
     }
-    writeln!(f)
-}
 
-fn write_headlines(
-    f: &mut fmt::Formatter<'_>,
-    headline_order: &[String],
-    headlines: &HashMap<String, String>,
-) -> fmt::Result {
-    for name in headline_order {
-        if let Some(value) = headlines.get(name) {
-            writeln!(
-                f,
-                "<!>{},{}",
-                name.escape_nasty_chars(),
-                value.escape_nasty_chars()
-            )?;
+    /// Applies `f` to every present cell in `column`, replacing each value.
+    ///
+    /// Missing cells are left missing. Returns `false` if the column does
+    /// not exist.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let mut view = Dataview::builder()
+    ///     .set_row_header("cpu")
+    ///     .add_value("cpu0", "usage", "42")
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(view.map_column("usage", |v| format!("{v}%")));
+    /// assert_eq!(view.value("cpu0", "usage"), Some(&"42%".to_string()));
+    /// ```
+    pub fn map_column<F: FnMut(&str) -> String>(&mut self, column: &str, mut f: F) -> bool {
+        if !self.column_order.iter().any(|c| c == column) {
+            return false;
         }
-    }
-    Ok(())
-}
 
-fn write_data_rows(
-    f: &mut fmt::Formatter<'_>,
-    rows: &[String],
-    columns: &[String],
-    values: &HashMap<(String, String), String>,
-) -> fmt::Result {
-    let number_of_rows = rows.len();
-    for (i, row) in rows.iter().enumerate() {
-        write!(f, "{}", row.escape_nasty_chars())?;
-        for col in columns {
-            write!(f, ",")?;
-            if let Some(value) = values.get(&(row.to_string(), col.to_string())) {
-                write!(f, "{}", value.escape_nasty_chars())?;
+        for row in &self.row_order {
+            if let Some(value) = self.values.get_mut(&(row.to_string(), column.to_string())) {
+                *value = f(value);
             }
         }
+        true
+    }
 
-        // Only write newline if this isn't the last row
-        if i < number_of_rows - 1 {
-            writeln!(f)?;
+    /// Rewrites every column name in upper case (`to_upper`) or lower case.
+    ///
+    /// Columns whose names become identical are merged, like
+    /// [`DataviewBuilder::merge_columns`]: the merged column takes the
+    /// position of the first of them, and for each row the cell from the
+    /// earliest column wins, with later columns only filling missing cells.
+    /// Column defaults are merged the same way.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let mut view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "Status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// view.normalize_column_case(true);
+    /// assert_eq!(view.to_string(), "host,STATUS\nweb-01,up");
+    /// ```
+    pub fn normalize_column_case(&mut self, to_upper: bool) {
+        let normalize = |s: &str| {
+            if to_upper {
+                s.to_uppercase()
+            } else {
+                s.to_lowercase()
+            }
+        };
+
+        let mut values = HashMap::with_capacity(self.values.len());
+        let mut column_defaults = HashMap::with_capacity(self.column_defaults.len());
+        for col in std::mem::take(&mut self.column_order) {
+            let normalized = normalize(&col);
+            for row in &self.row_order {
+                if let Some(value) = self.values.remove(&(row.clone(), col.clone())) {
+                    values
+                        .entry((row.clone(), normalized.clone()))
+                        .or_insert(value);
+                }
+            }
+            if let Some(default) = self.column_defaults.remove(&col) {
+                column_defaults.entry(normalized.clone()).or_insert(default);
+            }
+            if !self.column_order.contains(&normalized) {
+                self.column_order.push(normalized);
+            }
         }
+        self.values = values;
+        self.column_defaults = column_defaults;
     }
 
-    Ok(())
-}
+    /// Applies `f` to every present cell in the view, replacing each value.
+    ///
+    /// `f` receives the row name, column name and current value. This is the
+    /// view-wide version of [`Dataview::map_column`]; missing cells are left
+    /// missing.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let mut view = Dataview::builder()
+    ///     .set_row_header("cpu")
+    ///     .add_value("cpu0", "usage", "42")
+    ///     .add_value("cpu0", "idle", "58")
+    ///     .build()
+    ///     .unwrap();
+    /// view.map_cells(|_, column, value| format!("{column}={value}"));
+    /// assert_eq!(view.value("cpu0", "idle"), Some(&"idle=58".to_string()));
+    /// ```
+    pub fn map_cells<F: FnMut(&str, &str, &str) -> String>(&mut self, mut f: F) {
+        for row in &self.row_order {
+            for column in &self.column_order {
+                if let Some(value) = self.values.get_mut(&(row.to_string(), column.to_string())) {
+                    *value = f(row, column, value);
+                }
+            }
+        }
+    }
 
-impl fmt::Display for Dataview {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write_header_row(f, &self.row_header, &self.column_order)?;
-        write_headlines(f, &self.headline_order, &self.headlines)?;
-        write_data_rows(f, &self.row_order, &self.column_order, &self.values)
+    /// Returns the columns, in display order, that hold no data in any row.
+    ///
+    /// A column counts as empty when every row's cell is either missing or
+    /// an empty string, i.e. it renders blank throughout.
+    pub fn empty_columns(&self) -> Vec<&str> {
+        self.column_order
+            .iter()
+            .filter(|col| {
+                self.row_order
+                    .iter()
+                    .all(|row| self.value(row, col).is_none_or(|v| v.is_empty()))
+            })
+            .map(String::as_str)
+            .collect()
     }
-}
 
-impl Dataview {
-    /// Creates a new DataviewBuilder instance
+    /// Returns each row name, in display order, with the number of columns
+    /// it has populated, to spot suspiciously sparse rows.
     ///
-    /// This allows users to create a Dataview without explicitly importing DataviewBuilder
+    /// Like [`Dataview::empty_columns`], a cell counts only if it is present
+    /// and non-empty; column defaults are not counted.
     ///
     /// # Example
-    ///
     /// ```
     /// use geneos_toolkit::prelude::*;
-    /// let dataview = Dataview::builder()
-    ///     .set_row_header("ID")
-    ///     .add_headline("Total", "42")
-    ///     .add_value("1", "Name", "Alice")
-    ///     .build();
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .add_value("web-01", "mem", "20")
+    ///     .add_value("web-02", "cpu", "30")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.row_cell_counts(), vec![("web-01", 2), ("web-02", 1)]);
     /// ```
-    pub fn builder() -> DataviewBuilder {
-        DataviewBuilder::new()
+    pub fn row_cell_counts(&self) -> Vec<(&str, usize)> {
+        self.row_order
+            .iter()
+            .map(|row| {
+                let count = self
+                    .column_order
+                    .iter()
+                    .filter(|col| self.value(row, col).is_some_and(|v| !v.is_empty()))
+                    .count();
+                (row.as_str(), count)
+            })
+            .collect()
     }
-}
-
-/// A helper struct to build a row of data.
-///
-/// This allows constructing a row with multiple columns before adding it to the Dataview.
-#[derive(Debug, Clone, Default)]
-pub struct Row {
-    name: String,
-    cells: Vec<(String, String)>,
-}
 
-impl Row {
-    /// Creates a new Row with the given name (row identifier).
-    pub fn new(name: impl ToString) -> Self {
-        Self {
-            name: name.to_string(),
-            cells: Vec::new(),
+    /// Returns the distinct values in `column`, in the order they first appear
+    /// in the rows, or `None` if the column does not exist.
+    ///
+    /// Missing cells are skipped; column defaults are not included.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .add_value("web-02", "status", "down")
+    ///     .add_value("web-03", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.distinct_column_values("status"), Some(vec!["up", "down"]));
+    /// ```
+    pub fn distinct_column_values(&self, column: &str) -> Option<Vec<&str>> {
+        if !self.column_order.iter().any(|c| c == column) {
+            return None;
         }
-    }
 
-    /// Adds a cell (column and value) to the row, preserving insertion order.
-    pub fn add_cell(mut self, column: impl ToString, value: impl ToString) -> Self {
-        self.cells.push((column.to_string(), value.to_string()));
-        self
+        let mut distinct: Vec<&str> = Vec::new();
+        for value in self
+            .row_order
+            .iter()
+            .filter_map(|row| self.value(row, column))
+        {
+            if !distinct.contains(&value.as_str()) {
+                distinct.push(value);
+            }
+        }
+        Some(distinct)
     }
-}
 
-/// A Builder for the `Dataview` struct.
-#[derive(Debug, Clone)]
-pub struct DataviewBuilder {
-    row_header: Option<String>,
-    headlines: Option<HashMap<String, String>>,
-    values: Option<HashMap<(String, String), String>>,
-    headline_order: Vec<String>, // for the purpose of ordering the headlines
-    column_order: Vec<String>,   // for the purpose of ordering the columns
-    row_order: Vec<String>,      // for the purpose of ordering the rows
-    strip_unicode: bool,
-}
-
-impl Default for DataviewBuilder {
-    fn default() -> Self {
-        Self {
-            row_header: None,
-            headlines: None,
-            values: None,
-            headline_order: Vec::new(),
-            column_order: Vec::new(),
-            row_order: Vec::new(),
-            strip_unicode: true,
-        }
+    /// Returns a copy of this view with its rows sorted ascending by name.
+    ///
+    /// The original view keeps its insertion order.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-02", "status", "up")
+    ///     .add_value("web-01", "status", "down")
+    ///     .build()
+    ///     .unwrap();
+    /// let sorted = view.sorted_rows();
+    /// assert_eq!(sorted.row_order(), &["web-01", "web-02"]);
+    /// assert_eq!(view.row_order(), &["web-02", "web-01"]);
+    /// ```
+    pub fn sorted_rows(&self) -> Dataview {
+        let mut view = self.clone();
+        view.row_order.sort();
+        view
     }
-}
 
-impl DataviewBuilder {
-    /// Creates a new, empty builder.
-    pub fn new() -> Self {
-        Self::default()
+    /// Returns a copy of this view with its columns sorted ascending by name.
+    ///
+    /// The original view keeps its insertion order.
+    pub fn sorted_columns(&self) -> Dataview {
+        let mut view = self.clone();
+        view.column_order.sort();
+        view
     }
 
-    /// Controls whether Unicode control characters (categories Cc and Cf,
-    /// excluding ASCII whitespace) are stripped from all input strings.
-    /// Enabled by default. Set to `false` to preserve raw Unicode control characters.
-    pub fn strip_unicode_controls(mut self, strip: bool) -> Self {
-        self.strip_unicode = strip;
-        self
+    /// Splits this view into one view per column group, for views too wide
+    /// to publish as a single dataview.
+    ///
+    /// Each view keeps the row header, headlines, render options and every
+    /// row, in order, but only the columns named in its group, in this view's
+    /// column order. Columns that are in no group are dropped, as are group
+    /// entries naming columns this view does not have.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .add_value("web-01", "mem", "20")
+    ///     .add_value("web-01", "disk", "30")
+    ///     .build()
+    ///     .unwrap();
+    /// let views = view.split_by_columns(&[&["cpu", "mem"], &["disk"]]);
+    /// assert_eq!(views[0].to_string(), "host,cpu,mem\nweb-01,10,20");
+    /// assert_eq!(views[1].to_string(), "host,disk\nweb-01,30");
+    /// ```
+    pub fn split_by_columns(&self, groups: &[&[&str]]) -> Vec<Dataview> {
+        groups
+            .iter()
+            .map(|group| {
+                let column_order: Vec<String> = self
+                    .column_order
+                    .iter()
+                    .filter(|col| group.contains(&col.as_str()))
+                    .cloned()
+                    .collect();
+                let keep = |col: &String| column_order.contains(col);
+                Dataview {
+                    values: self
+                        .values
+                        .iter()
+                        .filter(|((_, col), _)| keep(col))
+                        .map(|(key, value)| (key.clone(), value.clone()))
+                        .collect(),
+                    column_defaults: self
+                        .column_defaults
+                        .iter()
+                        .filter(|(col, _)| keep(col))
+                        .map(|(col, value)| (col.clone(), value.clone()))
+                        .collect(),
+                    row_header: self.row_header.clone(),
+                    headlines: self.headlines.clone(),
+                    headline_order: self.headline_order.clone(),
+                    row_order: self.row_order.clone(),
+                    render_options: self.render_options.clone(),
+                    metadata: self.metadata.clone(),
+                    sanitizer: self.sanitizer,
+                    column_order,
+                }
+            })
+            .collect()
     }
 
-    /// Sanitize a string according to builder settings.
-    fn sanitize(&self, s: &str) -> String {
-        if self.strip_unicode {
-            strip_unicode_controls(s)
-        } else {
-            s.to_string()
+    /// Joins `other` onto this view column-wise, matching rows by name.
+    ///
+    /// The result has the union of both views' rows: this view's rows first,
+    /// then rows only `other` has. Rows present in both combine their cells.
+    /// `other`'s columns are appended after this view's. Headlines and column
+    /// defaults are combined the same way, with this view's taking
+    /// precedence; render options come from this view.
+    ///
+    /// Column conflicts are an error rather than an overwrite: if any column
+    /// exists in both views, returns [`MergeError::ColumnConflict`] naming
+    /// the first one. The row headers must be equal, otherwise returns
+    /// [`MergeError::RowHeaderMismatch`].
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let cpu = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .build()
+    ///     .unwrap();
+    /// let mem = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "mem", "20")
+    ///     .build()
+    ///     .unwrap();
+    /// let joined = cpu.join_columns(&mem).unwrap();
+    /// assert_eq!(joined.to_string(), "host,cpu,mem\nweb-01,10,20");
+    /// ```
+    pub fn join_columns(&self, other: &Dataview) -> Result<Dataview, MergeError> {
+        if self.row_header != other.row_header {
+            return Err(MergeError::RowHeaderMismatch {
+                left: self.row_header.clone(),
+                right: other.row_header.clone(),
+            });
+        }
+        if let Some(col) = other
+            .column_order
+            .iter()
+            .find(|col| self.column_order.contains(col))
+        {
+            return Err(MergeError::ColumnConflict(col.clone()));
         }
-    }
 
-    /// Sets the mandatory row header label.
-    pub fn set_row_header(mut self, row_header: &str) -> Self {
-        self.row_header = Some(self.sanitize(row_header));
-        self
+        let mut joined = self.clone();
+        joined
+            .column_order
+            .extend(other.column_order.iter().cloned());
+        for row in &other.row_order {
+            if !joined.row_order.contains(row) {
+                joined.row_order.push(row.clone());
+            }
+        }
+        joined.values.extend(
+            other
+                .values
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        for (col, value) in &other.column_defaults {
+            joined
+                .column_defaults
+                .entry(col.clone())
+                .or_insert_with(|| value.clone());
+        }
+        for key in &other.headline_order {
+            if !joined.headlines.contains_key(key) {
+                joined.headline_order.push(key.clone());
+                joined
+                    .headlines
+                    .insert(key.clone(), other.headlines[key].clone());
+            }
+        }
+        Ok(joined)
     }
 
-    /// Adds or replaces a headline value. Order is preserved by first insert.
-    pub fn add_headline<T: ToString>(mut self, key: &str, value: T) -> Self {
-        let key_string = self.sanitize(key);
-        let value_string = self.sanitize(&value.to_string());
-
-        let mut headlines: HashMap<String, String> = self.headlines.unwrap_or_default();
-
-        if !self.headline_order.contains(&key_string) {
-            self.headline_order.push(key_string.clone());
+    /// Returns the view's data as a rectangular table, e.g. for passing
+    /// across an FFI boundary.
+    ///
+    /// Every row has exactly one value per column, in column order. Missing
+    /// cells hold the column default if one is set, as when rendered, and an
+    /// empty string otherwise. Headlines are not included.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .add_value("web-02", "mem", "20")
+    ///     .build()
+    ///     .unwrap();
+    /// let flat = view.to_flat();
+    /// assert_eq!(flat.columns, ["cpu", "mem"]);
+    /// assert_eq!(flat.rows[1], ("web-02".to_string(), vec![String::new(), "20".to_string()]));
+    /// ```
+    pub fn to_flat(&self) -> FlatDataview {
+        let rows = self
+            .row_order
+            .iter()
+            .map(|row| {
+                let values = self
+                    .column_order
+                    .iter()
+                    .map(|col| {
+                        self.value(row, col)
+                            .or_else(|| self.column_defaults.get(col))
+                            .cloned()
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                (row.clone(), values)
+            })
+            .collect();
+        FlatDataview {
+            row_header: self.row_header.clone(),
+            columns: self.column_order.clone(),
+            rows,
         }
+    }
 
-        headlines.insert(key_string, value_string);
-        self.headlines = Some(headlines);
-        self
+    /// Renders the dataview and checks that every line has the expected number
+    /// of fields, i.e. that no delimiter escaped the escaping.
+    ///
+    /// The header and data lines must have one field per column plus the row
+    /// name; headline lines must have exactly two. Returns
+    /// `DataviewError::FieldCountMismatch` with the 1-based line number of the
+    /// first line that does not.
+    pub fn validate(&self) -> Result<(), DataviewError> {
+        let output = self.render_plain();
+        let columns = self.column_order.len() + 1;
+
+        for (idx, line) in output.split('\n').enumerate() {
+            let expected = if line.starts_with("<!>") { 2 } else { columns };
+            let found = count_fields(line);
+            if found != expected {
+                return Err(DataviewError::FieldCountMismatch {
+                    line: idx + 1,
+                    expected,
+                    found,
+                });
+            }
+        }
+        Ok(())
     }
 
-    /// Adds a single cell value at `row`/`column`, recording insertion order.
-    pub fn add_value<T: ToString>(mut self, row: &str, column: &str, value: T) -> Self {
-        let column_string = self.sanitize(column);
-        let row_string = self.sanitize(row);
-        let value_string = self.sanitize(&value.to_string());
+    /// Renders the dataview, parses the output back and checks that it matches,
+    /// catching escaping regressions.
+    ///
+    /// Rows and headlines are parsed with [`parse_rows`] and the same
+    /// un-escaping. A missing cell is expected to read back as its column
+    /// default, or as empty. Returns the first difference found.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("path")
+    ///     .add_value("C:\\temp", "note", "a,b")
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(view.assert_roundtrips().is_ok());
+    /// ```
+    pub fn assert_roundtrips(&self) -> Result<(), RoundtripError> {
+        fn check(
+            location: String,
+            expected: &str,
+            found: Option<&str>,
+        ) -> Result<(), RoundtripError> {
+            match found {
+                Some(found) if found == expected => Ok(()),
+                found => Err(RoundtripError::Mismatch {
+                    location,
+                    expected: expected.to_string(),
+                    found: found.unwrap_or("<missing>").to_string(),
+                }),
+            }
+        }
 
-        let mut values: HashMap<(String, String), String> = self.values.unwrap_or_default();
+        let rendered = self.render_plain();
+
+        let parsed_headlines: HashMap<String, String> = rendered
+            .split('\n')
+            .filter_map(|line| line.strip_prefix("<!>"))
+            .filter_map(|line| {
+                let mut fields = split_escaped(line, ',').into_iter();
+                Some((fields.next()?, fields.next().unwrap_or_default()))
+            })
+            .collect();
+        for key in &self.headline_order {
+            let expected = self.headlines.get(key).map(String::as_str).unwrap_or("");
+            check(
+                format!("headline '{key}'"),
+                expected,
+                parsed_headlines.get(key).map(String::as_str),
+            )?;
+        }
 
-        // Track columns in order of insertion (if new)
-        if !self.column_order.contains(&column_string) {
-            self.column_order.push(column_string.clone());
+        let mut rows = parse_rows(rendered.as_bytes());
+        let (row_header, header_cells) = rows.next().ok_or(ParseError::MissingHeader)??;
+        check("row header".into(), &self.row_header, Some(&row_header))?;
+        check(
+            "column count".into(),
+            &self.column_order.len().to_string(),
+            Some(&header_cells.len().to_string()),
+        )?;
+        for (idx, (col, (parsed, _))) in self.column_order.iter().zip(&header_cells).enumerate() {
+            check(format!("column {}", idx + 1), col, Some(parsed))?;
         }
 
-        // Track rows in order of insertion (if new)
-        if !self.row_order.contains(&row_string) {
-            self.row_order.push(row_string.clone());
+        let mut expected_rows = self.row_order.iter();
+        for parsed in rows {
+            let (row, cells) = parsed?;
+            let Some(expected_row) = expected_rows.next() else {
+                return Err(RoundtripError::Mismatch {
+                    location: "rows".into(),
+                    expected: "<end>".into(),
+                    found: row,
+                });
+            };
+            check("row name".into(), expected_row, Some(&row))?;
+            for (idx, col) in self.column_order.iter().enumerate() {
+                let expected = self
+                    .value(expected_row, col)
+                    .or_else(|| self.column_defaults.get(col))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                check(
+                    format!("cell '{expected_row}'/'{col}'"),
+                    expected,
+                    cells.get(idx).map(|(_, value)| value.as_str()),
+                )?;
+            }
+        }
+        if let Some(missing) = expected_rows.next() {
+            return Err(RoundtripError::Mismatch {
+                location: "rows".into(),
+                expected: missing.clone(),
+                found: "<end>".into(),
+            });
         }
 
-        values.insert((row_string, column_string), value_string);
-        self.values = Some(values);
-        self
+        Ok(())
     }
 
-    /// Adds a complete row to the Dataview.
+    /// Returns a copy of the dataview with its row header replaced by
+    /// `new_header`, with control characters stripped as by
+    /// [`DataviewBuilder::set_row_header`].
     ///
-    /// This is a convenience method to add multiple values for the same row at once.
+    /// Fails with `DataviewError::EmptyName` if `new_header` is empty and with
+    /// `DataviewError::RowHeaderCollision` if it equals an existing column name.
     ///
     /// # Example
     /// ```
     /// use geneos_toolkit::prelude::*;
-    ///
-    /// let row = Row::new("process1")
-    ///     .add_cell("Status", "Running")
-    ///     .add_cell("CPU", "2.5%");
-    ///
-    /// let dataview = Dataview::builder()
-    ///     .set_row_header("Process")
-    ///     .add_row(row)
-    ///     .build();
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// let renamed = view.with_row_header("server").unwrap();
+    /// assert_eq!(renamed.to_string(), "server,status\nweb-01,up");
     /// ```
-    pub fn add_row(mut self, row: Row) -> Self {
-        for (col, val) in row.cells {
-            self = self.add_value(&row.name, &col, &val);
+    pub fn with_row_header(&self, new_header: &str) -> Result<Dataview, DataviewError> {
+        let new_header = self.sanitizer.name(new_header);
+        if new_header.is_empty() {
+            return Err(DataviewError::empty_name("row header", None));
         }
-        self
+        if self.column_order.contains(&new_header) {
+            return Err(DataviewError::RowHeaderCollision(new_header));
+        }
+        Ok(Dataview {
+            row_header: new_header,
+            ..self.clone()
+        })
     }
 
-    /// Sorts rows in ascending order by row name. Opt-in; default is insertion order.
-    /// Sorts rows in ascending order by row name. Opt-in; default is insertion order.
-    pub fn sort_rows(mut self) -> Self {
-        self.row_order.sort();
-        self
-    }
+    /// Sets each `(row, column, value)` triple, appending new rows and columns
+    /// in the order they first appear.
+    ///
+    /// Names and values are cleaned up the way the builder cleaned them, so
+    /// control characters are stripped unless the view was built with
+    /// `strip_unicode_controls(false)`, and values are trimmed if it was built
+    /// with `trim_values()`. All triples are validated before any is
+    /// applied, so an empty row or column name leaves the view untouched.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let mut view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// view.apply_update(&[
+    ///     ("web-01".to_string(), "status".to_string(), "down".to_string()),
+    ///     ("web-02".to_string(), "status".to_string(), "up".to_string()),
+    /// ])
+    /// .unwrap();
+    /// assert_eq!(view.to_string(), "host,status\nweb-01,down\nweb-02,up");
+    /// ```
+    pub fn apply_update(
+        &mut self,
+        updates: &[(String, String, String)],
+    ) -> Result<(), DataviewError> {
+        let updates: Vec<(String, String, String)> = updates
+            .iter()
+            .map(|(row, column, value)| {
+                (
+                    self.sanitizer.name(row),
+                    self.sanitizer.name(column),
+                    self.sanitizer.value(value),
+                )
+            })
+            .collect();
+        for (row, column, _) in &updates {
+            if row.is_empty() {
+                return Err(DataviewError::empty_name(
+                    "row",
+                    Some(format!("column '{column}'")),
+                ));
+            }
+            if column.is_empty() {
+                return Err(DataviewError::empty_name(
+                    "column",
+                    Some(format!("row '{row}'")),
+                ));
+            }
+        }
 
-    /// Sorts rows using a key selector. Opt-in; default is insertion order.
-    pub fn sort_rows_by<K, F>(mut self, mut f: F) -> Self
-    where
-        K: Ord,
-        F: FnMut(&str) -> K,
-    {
-        self.row_order.sort_by_key(|row| f(row));
-        self
+        for (row, column, value) in updates {
+            if !self.column_order.contains(&column) {
+                self.column_order.push(column.clone());
+            }
+            if !self.row_order.contains(&row) {
+                self.row_order.push(row.clone());
+            }
+            self.values.insert((row, column), value);
+        }
+        Ok(())
     }
 
-    /// Sorts rows using a custom comparator. Opt-in; default is insertion order.
-    pub fn sort_rows_with<F>(mut self, mut cmp: F) -> Self
-    where
-        F: FnMut(&str, &str) -> std::cmp::Ordering,
-    {
-        self.row_order.sort_by(|a, b| cmp(a, b));
-        self
+    /// Adds or updates a single cell, for data that arrives after the view
+    /// was built. A new column or row is appended at the end, and names and
+    /// the value are cleaned up, like [`Dataview::apply_update`].
+    ///
+    /// Fails with `DataviewError::EmptyName` if `row` or `column` is empty,
+    /// leaving the view untouched.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let mut view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// view.set_value("web-01", "latency", 12).unwrap();
+    /// assert_eq!(view.to_string(), "host,status,latency\nweb-01,up,12");
+    /// ```
+    pub fn set_value(
+        &mut self,
+        row: &str,
+        column: &str,
+        value: impl ToString,
+    ) -> Result<(), DataviewError> {
+        self.apply_update(&[(row.to_string(), column.to_string(), value.to_string())])
     }
 
-    /// Builds the `Dataview`, consuming the builder.
+    /// Renders only the headlines and data rows that differ from `previous`,
+    /// with the view's render options.
     ///
-    /// The `row_header` must be set before the build or a panic will occur.
-    /// There must be at least one value.
-    /// Headlines are optional.
-    ///
-    /// The order of the columns and rows is determined by the order in which they are added through
-    /// values using the `add_value` method.
+    /// The output is the header row, a [`DELTA_MARKER`] headline holding the
+    /// number of changed rows, each headline that is new or has a new value,
+    /// a [`DELTA_REMOVED_MARKER`] line naming each removed row, then each
+    /// changed row in display order. A row is changed if it is new or any of
+    /// its cells differs from `previous`, and removed if it is only in
+    /// `previous`. Removed headlines are not reported. Columns hidden by
+    /// `hide_empty_columns` are those hidden in the full output, so both
+    /// have the same header row.
     ///
-    /// The order of headlines is determined by the order in which they are added through the
-    /// `add_headline` method.
+    /// Fails with `DataviewError::ReservedHeadline` if the view has a
+    /// headline named like one of the markers.
     ///
-    /// Example:
-    /// ```rust
+    /// # Example
+    /// ```
     /// use geneos_toolkit::prelude::*;
-    ///
-    /// let view: Dataview = Dataview::builder()
-    ///     .set_row_header("Name")
-    ///     .add_headline("AverageAge", "30")
-    ///     .add_value("Anna", "Age", "30")
-    ///     .add_value("Bertil", "Age", "20")
-    ///     .add_value("Caesar", "Age", "40")
+    /// let before = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .add_value("web-02", "status", "up")
+    ///     .add_value("web-03", "status", "up")
     ///     .build()
     ///     .unwrap();
-    ///
+    /// let after = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .add_value("web-02", "status", "down")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     after.render_delta(&before).unwrap(),
+    ///     "host,status\n<!>delta,1\n<!>deltaRemoved,web-03\nweb-02,down"
+    /// );
     /// ```
-    pub fn build(self) -> Result<Dataview, DataviewError> {
-        let row_header = self.row_header.ok_or(DataviewError::MissingRowHeader)?;
-
-        if row_header.is_empty() {
-            return Err(DataviewError::EmptyName("row header".into()));
-        }
-
-        let values = self.values.ok_or(DataviewError::MissingValue)?;
-
-        for row in &self.row_order {
-            if row.is_empty() {
-                return Err(DataviewError::EmptyName("row".into()));
+    pub fn render_delta(&self, previous: &Dataview) -> Result<String, DataviewError> {
+        for marker in [DELTA_MARKER, DELTA_REMOVED_MARKER] {
+            if self.headlines.contains_key(marker) {
+                return Err(DataviewError::ReservedHeadline(marker.to_string()));
             }
         }
 
-        for col in &self.column_order {
-            if col.is_empty() {
-                return Err(DataviewError::EmptyName("column".into()));
+        let changed: Vec<String> = self
+            .row_order
+            .iter()
+            .filter(|row| {
+                !previous.row_order.contains(row)
+                    || self
+                        .column_order
+                        .iter()
+                        .any(|col| self.value(row, col) != previous.value(row, col))
+            })
+            .cloned()
+            .collect();
+        let removed: Vec<String> = previous
+            .row_order
+            .iter()
+            .filter(|row| !self.row_order.contains(row))
+            .cloned()
+            .collect();
+
+        let mut headline_order = vec![DELTA_MARKER.to_string()];
+        let mut headlines = HashMap::from([(DELTA_MARKER.to_string(), changed.len().to_string())]);
+        for key in &self.headline_order {
+            if let Some(value) = self.headlines.get(key) {
+                if previous.headlines.get(key) != Some(value) {
+                    headline_order.push(key.clone());
+                    headlines.insert(key.clone(), value.clone());
+                }
             }
         }
 
-        if let Some(ref headlines) = self.headlines {
-            for key in headlines.keys() {
-                if key.is_empty() {
-                    return Err(DataviewError::EmptyName("headline".into()));
-                }
-            }
+        let changed_set: HashSet<&String> = changed.iter().collect();
+        let delta = Dataview {
+            row_header: self.row_header.clone(),
+            headlines,
+            headline_order,
+            values: self
+                .values
+                .iter()
+                .filter(|((row, _), _)| changed_set.contains(row))
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect(),
+            column_order: visible_columns(self, &self.render_options),
+            row_order: changed.clone(),
+            column_defaults: self.column_defaults.clone(),
+            render_options: RenderOptions {
+                hide_empty_columns: false,
+                ..self.render_options.clone()
+            },
+            metadata: BTreeMap::new(),
+            sanitizer: self.sanitizer,
+        };
+        Ok(RenderView {
+            view: &delta,
+            options: &delta.render_options,
+            removed: &removed,
         }
+        .to_string())
+    }
 
-        Ok(Dataview {
-            row_header,
-            headlines: self.headlines.unwrap_or_default(),
-            headline_order: self.headline_order,
-            values,
-            column_order: self.column_order,
-            row_order: self.row_order,
-        })
+    /// Renders only the headline lines (`<!>name,value`), in order, without
+    /// the header row or data rows. Like the full render, there is no
+    /// trailing newline, and the view's `drop_empty_headlines` and
+    /// `line_ending` render options apply.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_headline("region", "eu")
+    ///     .add_headline("status", "ok")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.headlines_to_string(), "<!>region,eu\n<!>status,ok");
+    /// ```
+    pub fn headlines_to_string(&self) -> String {
+        let mut out = HeadlinesView {
+            view: self,
+            options: &self.render_options,
+        }
+        .to_string();
+        let terminator = match self.render_options.line_ending {
+            LineEnding::Lf => 1,
+            LineEnding::Crlf => 2,
+        };
+        out.truncate(out.len().saturating_sub(terminator));
+        out
     }
 }
 
-/// Prints the result of a Dataview operation and exits the program.
-///
-/// # Arguments
-/// - `dataview`: The `Result` of a Dataview operation, holding either a `Dataview` or a `DataviewError`.
-///
-/// # Returns
-/// - Exits the program with a status code of 0 if successful, or 1 if an error occurred.
-///
-/// # Example
-/// ```rust
-/// use geneos_toolkit::prelude::*;
+/// A dataview's data as a rectangular table, returned by [`Dataview::to_flat`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FlatDataview {
+    pub row_header: String,
+    pub columns: Vec<String>,
+    /// Each row's name and its values, one per entry in `columns`.
+    pub rows: Vec<(String, Vec<String>)>,
+}
+
+/// Renders a dataview's headline lines with the given options.
+struct HeadlinesView<'a> {
+    view: &'a Dataview,
+    options: &'a RenderOptions,
+}
+
+impl fmt::Display for HeadlinesView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headline_order = visible_headlines(self.view, self.options);
+        let headlines = &self.view.headlines;
+        match self.options.line_ending {
+            LineEnding::Lf => write_headlines(f, &headline_order, headlines),
+            LineEnding::Crlf => write_headlines(&mut Crlf(f), &headline_order, headlines),
+        }
+    }
+}
+
+/// Headline key the gateway maps to a managed entity, set with
+/// [`DataviewBuilder::set_managed_entity`].
+pub const MANAGED_ENTITY_HEADLINE: &str = "_entity";
+
+/// Headline key that marks output produced by [`Dataview::render_delta`].
+pub const DELTA_MARKER: &str = "delta";
+
+/// Headline key of the lines naming rows removed since the previous view in
+/// [`Dataview::render_delta`] output, one line per row.
+pub const DELTA_REMOVED_MARKER: &str = "deltaRemoved";
+
+/// The builder's cleanup settings, kept on the built view so
+/// [`Dataview::apply_update`] cleans later data the same way.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct Sanitizer {
+    strip_unicode: bool,
+    trim_values: bool,
+}
+
+impl Default for Sanitizer {
+    fn default() -> Self {
+        Self {
+            strip_unicode: true,
+            trim_values: false,
+        }
+    }
+}
+
+impl Sanitizer {
+    /// Cleans a row, column or headline name.
+    fn name(&self, s: &str) -> String {
+        if self.strip_unicode {
+            strip_unicode_controls(s)
+        } else {
+            s.to_string()
+        }
+    }
+
+    /// Cleans a cell value.
+    fn value(&self, s: &str) -> String {
+        let value = self.name(s);
+        if self.trim_values {
+            value.trim_ascii().to_string()
+        } else {
+            value
+        }
+    }
+}
+
+/// Strips Unicode control characters (categories Cc and Cf) except ASCII
+/// whitespace (tab, newline, carriage return, space). Newlines and carriage
+/// returns are subsequently escaped by `escape_nasty_chars`.
+fn strip_unicode_controls(s: &str) -> String {
+    s.chars().filter(|&c| !is_stripped_control(c)).collect()
+}
+
+/// Returns `true` for characters removed by `strip_unicode_controls`.
+fn is_stripped_control(c: char) -> bool {
+    if c == '\t' || c == '\n' || c == '\r' || c == ' ' {
+        return false;
+    }
+    c.is_control() || is_unicode_format_char(c)
+}
+
+/// Returns `true` for Unicode Cf (format) characters — RTL override, zero-width
+/// space, BOM, etc. Rust's `char::is_control()` covers Cc; this covers Cf.
+fn is_unicode_format_char(c: char) -> bool {
+    matches!(c as u32,
+        0x00AD              // SOFT HYPHEN
+        | 0x0600..=0x0605   // Arabic format chars
+        | 0x061C            // ARABIC LETTER MARK
+        | 0x06DD            // ARABIC END OF AYAH
+        | 0x070F            // SYRIAC ABBREVIATION MARK
+        | 0x08E2            // ARABIC DISPUTED END OF AYAH
+        | 0x180E            // MONGOLIAN VOWEL SEPARATOR
+        | 0x200B..=0x200F   // Zero-width space, joiners, LTR/RTL marks
+        | 0x202A..=0x202E   // Directional formatting
+        | 0x2060..=0x2064   // Word joiner, invisible operators
+        | 0x2066..=0x206F   // Directional isolates, deprecated chars
+        | 0xFEFF            // BOM / ZWNBSP
+        | 0xFFF9..=0xFFFB   // Interlinear annotations
+        | 0x110BD           // KAITHI NUMBER SIGN
+        | 0x110CD           // KAITHI NUMBER SIGN ABOVE
+        | 0x13430..=0x13438 // Egyptian hieroglyph format
+        | 0x1BCA0..=0x1BCA3 // Shorthand format controls
+        | 0x1D173..=0x1D17A // Musical symbol format
+        | 0xE0001           // LANGUAGE TAG
+        | 0xE0020..=0xE007F // TAG characters
+    )
+}
+
+trait GeneosEscaping {
+    fn escape_nasty_chars(&self) -> String;
+}
+
+impl GeneosEscaping for str {
+    fn escape_nasty_chars(&self) -> String {
+        let mut output = String::with_capacity(self.len());
+
+        // C1: Escape <!> at string start to prevent headline injection
+        let s = if let Some(rest) = self.strip_prefix("<!>") {
+            output.push_str("\\<!>");
+            rest
+        } else {
+            self
+        };
+
+        for c in s.chars() {
+            match c {
+                '\\' => output.push_str("\\\\"),
+                ',' => output.push_str("\\,"),
+                '\n' => output.push_str("\\n"),
+                '\r' => output.push_str("\\r"),
+                '\0' => output.push_str("\\0"),
+                c => output.push(c),
+            }
+        }
+        output
+    }
+}
+
+/// Counts the comma-separated fields in a rendered line, treating a
+/// backslash as escaping the character that follows it.
+fn count_fields(line: &str) -> usize {
+    let mut fields = 1;
+    let mut escaped = false;
+    for c in line.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == ',' {
+            fields += 1;
+        }
+    }
+    fields
+}
+
+/// Splits a line on unescaped `delimiter`s and reverses the toolkit escaping
+/// in each field.
 ///
-/// let dataview = Dataview::builder()
-///    .set_row_header("ID")
-///    .add_headline("Total", "42")
-///    .add_value("1", "Name", "Alice")
-///    .build();
+/// This is the inverse of the escaping applied when rendering: `\,` (or a
+/// backslash before any other character, including `delimiter`) yields that
+/// character, `\\` a backslash, and `\n`, `\r` and `\0` the corresponding
+/// control characters. A trailing delimiter yields a trailing empty field.
 ///
-/// print_result_and_exit(dataview)
+/// # Example
 /// ```
-/// Prints the dataview on success or an error on failure, then exits the process.
-pub fn print_result_and_exit(dataview: Result<Dataview, DataviewError>) -> ! {
-    match dataview {
-        Ok(v) => {
-            println!("{v}");
-            std::process::exit(0)
+/// use geneos_toolkit::dataview::split_escaped;
+/// assert_eq!(split_escaped(r"a\,b,c\\,", ','), vec!["a,b", "c\\", ""]);
+/// assert_eq!(split_escaped("a\tb", '\t'), vec!["a", "b"]);
+/// ```
+pub fn split_escaped(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => field.push('\n'),
+                Some('r') => field.push('\r'),
+                Some('0') => field.push('\0'),
+                Some(other) => field.push(other),
+                None => field.push('\\'),
+            },
+            c if c == delimiter => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
         }
-        Err(e) => {
-            eprintln!("ERROR: {e}");
-            std::process::exit(1)
+    }
+    fields.push(field);
+    fields
+}
+
+fn write_header_row(f: &mut impl fmt::Write, row_header: &str, columns: &[String]) -> fmt::Result {
+    write!(f, "{}", row_header.escape_nasty_chars())?;
+    for col in columns {
+        write!(f, ",{}", col.escape_nasty_chars())?;
+    }
+    writeln!(f)
+}
+
+fn write_headlines(
+    f: &mut impl fmt::Write,
+    headline_order: &[String],
+    headlines: &HashMap<String, String>,
+) -> fmt::Result {
+    for name in headline_order {
+        if let Some(value) = headlines.get(name) {
+            writeln!(
+                f,
+                "<!>{},{}",
+                name.escape_nasty_chars(),
+                value.escape_nasty_chars()
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_data_rows(
+    f: &mut fmt::Formatter<'_>,
+    rows: &[String],
+    columns: &[String],
+    values: &HashMap<(String, String), String>,
+    column_defaults: &HashMap<String, String>,
+) -> fmt::Result {
+    let number_of_rows = rows.len();
+    for (i, row) in rows.iter().enumerate() {
+        write!(f, "{}", row.escape_nasty_chars())?;
+        for col in columns {
+            write!(f, ",")?;
+            let value = values
+                .get(&(row.to_string(), col.to_string()))
+                .or_else(|| column_defaults.get(col));
+            if let Some(value) = value {
+                write!(f, "{}", value.escape_nasty_chars())?;
+            }
+        }
+
+        // Only write newline if this isn't the last row
+        if i < number_of_rows - 1 {
+            writeln!(f)?;
+        }
+    }
+
+    Ok(())
+}
+
+impl fmt::Display for Dataview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        RenderView {
+            view: self,
+            options: &self.render_options,
+            removed: &[],
+        }
+        .fmt(f)
+    }
+}
+
+/// Renders a dataview with the given options.
+struct RenderView<'a> {
+    view: &'a Dataview,
+    options: &'a RenderOptions,
+    /// Rows written as [`DELTA_REMOVED_MARKER`] lines after the headlines,
+    /// for [`Dataview::render_delta`].
+    removed: &'a [String],
+}
+
+/// Returns the headlines shown with `options`, in display order.
+fn visible_headlines(view: &Dataview, options: &RenderOptions) -> Vec<String> {
+    view.headline_order
+        .iter()
+        .filter(|key| {
+            !options.drop_empty_headlines || view.headlines.get(*key).is_some_and(|v| !v.is_empty())
+        })
+        .cloned()
+        .collect()
+}
+
+/// Returns the columns shown with `options`, in display order.
+fn visible_columns(view: &Dataview, options: &RenderOptions) -> Vec<String> {
+    view.column_order
+        .iter()
+        .filter(|col| {
+            !options.hide_empty_columns
+                || view.row_order.iter().any(|row| {
+                    view.value(row, col)
+                        .or_else(|| view.column_defaults.get(*col))
+                        .is_some_and(|v| !v.is_empty())
+                })
+        })
+        .cloned()
+        .collect()
+}
+
+impl fmt::Display for RenderView<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let view = self.view;
+        let dropped: Vec<String>;
+        let headline_order = if self.options.drop_empty_headlines {
+            dropped = visible_headlines(view, self.options);
+            &dropped
+        } else {
+            &view.headline_order
+        };
+        let shown: Vec<String>;
+        let columns = if self.options.hide_empty_columns {
+            shown = visible_columns(view, self.options);
+            &shown
+        } else {
+            &view.column_order
+        };
+        let lines = RenderLines {
+            view,
+            row_header: self
+                .options
+                .row_header_alias
+                .as_ref()
+                .unwrap_or(&view.row_header),
+            columns,
+            headline_order,
+            removed: self.removed,
+        };
+
+        if self.options.include_bom {
+            f.write_str(UTF8_BOM)?;
+        }
+        match self.options.line_ending {
+            LineEnding::Lf => lines.fmt(f),
+            LineEnding::Crlf => fmt::write(&mut Crlf(f), format_args!("{lines}")),
+        }
+    }
+}
+
+/// Renders the lines of a dataview with the given headlines.
+struct RenderLines<'a> {
+    view: &'a Dataview,
+    row_header: &'a str,
+    columns: &'a [String],
+    headline_order: &'a [String],
+    removed: &'a [String],
+}
+
+impl RenderLines<'_> {
+    /// Writes the header row, headlines and removed-row lines, each ending
+    /// in a newline.
+    fn write_head(&self, out: &mut impl fmt::Write) -> fmt::Result {
+        write_header_row(out, self.row_header, self.columns)?;
+        write_headlines(out, self.headline_order, &self.view.headlines)?;
+        for row in self.removed {
+            writeln!(
+                out,
+                "<!>{DELTA_REMOVED_MARKER},{}",
+                row.escape_nasty_chars()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for RenderLines<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let view = self.view;
+        if view.row_order.is_empty() {
+            // Without data rows the last line has no terminator either
+            let mut head = String::new();
+            self.write_head(&mut head)?;
+            head.pop();
+            return f.write_str(&head);
+        }
+        self.write_head(f)?;
+        write_data_rows(
+            f,
+            &view.row_order,
+            self.columns,
+            &view.values,
+            &view.column_defaults,
+        )
+    }
+}
+
+/// Writes every `\n` as `\r\n`.
+///
+/// Rendered values never contain a raw line break, so every `\n` in the
+/// output is a line terminator.
+struct Crlf<'a, 'b>(&'a mut fmt::Formatter<'b>);
+
+impl fmt::Write for Crlf<'_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut lines = s.split('\n');
+        self.0.write_str(lines.next().unwrap_or_default())?;
+        for line in lines {
+            self.0.write_str("\r\n")?;
+            self.0.write_str(line)?;
         }
+        Ok(())
+    }
+}
+
+/// Output options for rendering a dataview.
+///
+/// Options stored with [`DataviewBuilder::render_options`] are used by
+/// `Display` and [`Dataview::write_to`]; [`Dataview::write_to_with`] applies
+/// options for a single write instead.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct RenderOptions {
+    /// Prepends the UTF-8 byte order mark (`EF BB BF`) to the output. Some
+    /// Windows tools, such as Excel, need it to detect the encoding.
+    pub include_bom: bool,
+    /// Skips headlines whose value is empty, since some consumers reject a
+    /// bare `<!>name,` line. The remaining headlines keep their order.
+    pub drop_empty_headlines: bool,
+    /// Terminator written between lines. Line breaks inside values are
+    /// always escaped as `\n` and `\r`, whichever ending is chosen.
+    pub line_ending: LineEnding,
+    /// Label printed in place of the row header, e.g. a generic `rowName`.
+    /// Only the output changes: [`Dataview::row_header`] and value lookups
+    /// still use the real row header.
+    pub row_header_alias: Option<String>,
+    /// Skips columns where no row has a non-empty value, counting column
+    /// defaults, e.g. declared columns that were never filled. The stored
+    /// data is unchanged.
+    pub hide_empty_columns: bool,
+}
+
+/// Line terminator used when rendering a dataview.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum LineEnding {
+    /// `\n`, the default.
+    #[default]
+    Lf,
+    /// `\r\n`, for Windows consumers that expect it.
+    Crlf,
+}
+
+const UTF8_BOM: &str = "\u{FEFF}";
+
+impl Dataview {
+    /// Writes the rendered dataview to `writer`, exactly as `Display` renders it.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("id")
+    ///     .add_value("r1", "c1", "v1")
+    ///     .build()
+    ///     .unwrap();
+    /// let mut out = Vec::new();
+    /// view.write_to(&mut out).unwrap();
+    /// assert_eq!(out, b"id,c1\nr1,v1");
+    /// ```
+    pub fn write_to<W: io::Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.write_to_with(writer, &self.render_options)
+    }
+
+    /// Writes the rendered dataview to `writer`, applying `options` instead
+    /// of the view's own render options.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::RenderOptions;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("id")
+    ///     .add_value("r1", "c1", "v1")
+    ///     .build()
+    ///     .unwrap();
+    /// let mut out = Vec::new();
+    /// let options = RenderOptions {
+    ///     include_bom: true,
+    ///     ..RenderOptions::default()
+    /// };
+    /// view.write_to_with(&mut out, &options).unwrap();
+    /// assert_eq!(out, b"\xEF\xBB\xBFid,c1\nr1,v1");
+    /// ```
+    pub fn write_to_with<W: io::Write>(
+        &self,
+        writer: &mut W,
+        options: &RenderOptions,
+    ) -> io::Result<()> {
+        let view = RenderView {
+            view: self,
+            options,
+            removed: &[],
+        };
+        write!(writer, "{view}")
+    }
+
+    /// Clears `buf` and renders the dataview into it, exactly as `Display`
+    /// renders it. Reusing one buffer across sampling cycles avoids
+    /// allocating a new `String` each time.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("id")
+    ///     .add_value("r1", "c1", "v1")
+    ///     .build()
+    ///     .unwrap();
+    /// let mut buf = String::from("stale");
+    /// view.render_into(&mut buf);
+    /// assert_eq!(buf, "id,c1\nr1,v1");
+    /// ```
+    pub fn render_into(&self, buf: &mut String) {
+        use fmt::Write as _;
+        buf.clear();
+        write!(buf, "{self}").expect("writing to a String cannot fail");
+    }
+
+    /// Returns the number of bytes the rendered dataview occupies, including
+    /// escaping, without building the full output string.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("id")
+    ///     .add_value("r1", "c1", "a,b")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.rendered_len(), view.to_string().len());
+    /// ```
+    pub fn rendered_len(&self) -> usize {
+        let mut counter = ByteCounter(0);
+        fmt::write(&mut counter, format_args!("{self}")).expect("counting bytes cannot fail");
+        counter.0
+    }
+
+    /// Renders the dataview as a GitHub-flavored Markdown table.
+    ///
+    /// Headlines, if any, are rendered as a bullet list above the table.
+    /// Pipes are escaped as `\|` and line breaks become `<br>`.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_headline("region", "eu")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     view.to_markdown(),
+    ///     "- region: eu\n\n| host | status |\n| --- | --- |\n| web-01 | up |\n"
+    /// );
+    /// ```
+    pub fn to_markdown(&self) -> String {
+        fn cell(s: &str) -> String {
+            s.replace('|', "\\|")
+                .replace("\r\n", "<br>")
+                .replace(['\n', '\r'], "<br>")
+        }
+
+        let mut out = String::new();
+        for key in &self.headline_order {
+            if let Some(value) = self.headlines.get(key) {
+                out.push_str(&format!("- {}: {}\n", cell(key), cell(value)));
+            }
+        }
+        if !self.headline_order.is_empty() {
+            out.push('\n');
+        }
+
+        out.push_str(&format!("| {} |", cell(&self.row_header)));
+        for col in &self.column_order {
+            out.push_str(&format!(" {} |", cell(col)));
+        }
+        out.push_str("\n|");
+        for _ in 0..=self.column_order.len() {
+            out.push_str(" --- |");
+        }
+        out.push('\n');
+
+        for row in &self.row_order {
+            out.push_str(&format!("| {} |", cell(row)));
+            for col in &self.column_order {
+                let value = self
+                    .value(row, col)
+                    .or_else(|| self.column_defaults.get(col))
+                    .map(String::as_str)
+                    .unwrap_or("");
+                out.push_str(&format!(" {} |", cell(value)));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// Renders the data rows as newline-delimited JSON, one object per row,
+    /// each line ending in `\n`.
+    ///
+    /// Each object maps the row header to the row name, then each column to
+    /// its value, in column order. Missing cells take the column default, or
+    /// are left out if there is none. Headlines are not included; see
+    /// [`Dataview::to_ndjson_with_headlines`].
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .add_value("web-02", "note", "say \"hi\"")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     view.to_ndjson(),
+    ///     "{\"host\":\"web-01\",\"status\":\"up\"}\n\
+    ///      {\"host\":\"web-02\",\"note\":\"say \\\"hi\\\"\"}\n"
+    /// );
+    /// ```
+    pub fn to_ndjson(&self) -> String {
+        let mut out = String::new();
+        for row in &self.row_order {
+            out.push('{');
+            push_json_pair(&mut out, &self.row_header, row);
+            for col in &self.column_order {
+                if let Some(value) = self
+                    .value(row, col)
+                    .or_else(|| self.column_defaults.get(col))
+                {
+                    out.push(',');
+                    push_json_pair(&mut out, col, value);
+                }
+            }
+            out.push_str("}\n");
+        }
+        out
+    }
+
+    /// Renders the view like [`Dataview::to_ndjson`], preceded by one line
+    /// holding the headlines as `{"headlines":{"name":"value",...}}`, in
+    /// order.
+    pub fn to_ndjson_with_headlines(&self) -> String {
+        let mut out = String::from("{\"headlines\":{");
+        for (i, key) in self.headline_order.iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            let value = self.headlines.get(key).map(String::as_str).unwrap_or("");
+            push_json_pair(&mut out, key, value);
+        }
+        out.push_str("}}\n");
+        out.push_str(&self.to_ndjson());
+        out
+    }
+}
+
+/// Appends `"key":"value"` to `out`, escaping both as JSON strings.
+fn push_json_pair(out: &mut String, key: &str, value: &str) {
+    push_json_string(out, key);
+    out.push(':');
+    push_json_string(out, value);
+}
+
+fn push_json_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(feature = "prometheus")]
+impl Dataview {
+    /// Renders the numeric cells in the Prometheus text exposition format.
+    ///
+    /// Each column becomes a gauge named `<metric_prefix>_<column>`, with one
+    /// sample per row labelled `row="<row name>"`. Only cells holding a finite
+    /// number are emitted; missing cells, column defaults and columns with no
+    /// numeric cells are skipped. Characters not allowed in metric names become `_`,
+    /// and label values are escaped. If two columns map to the same metric
+    /// name, the later one gets a `_2`, `_3`, ... suffix, so every metric
+    /// family is declared once.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "42.5")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     view.to_prometheus("geneos"),
+    ///     "# TYPE geneos_cpu gauge\ngeneos_cpu{row=\"web-01\"} 42.5\n"
+    /// );
+    /// ```
+    pub fn to_prometheus(&self, metric_prefix: &str) -> String {
+        fn metric_name(prefix: &str, column: &str) -> String {
+            let raw = if prefix.is_empty() {
+                column.to_string()
+            } else {
+                format!("{prefix}_{column}")
+            };
+            let mut name: String = raw
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect();
+            if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_' || c == ':') {
+                name.insert(0, '_');
+            }
+            name
+        }
+
+        fn label_value(s: &str) -> String {
+            s.replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+        }
+
+        let mut used = HashSet::new();
+        let names: Vec<String> = self
+            .column_order
+            .iter()
+            .map(|col| {
+                let base = metric_name(metric_prefix, col);
+                let mut name = base.clone();
+                let mut suffix = 2;
+                while !used.insert(name.clone()) {
+                    name = format!("{base}_{suffix}");
+                    suffix += 1;
+                }
+                name
+            })
+            .collect();
+
+        let mut out = String::new();
+        for (col, name) in self.column_order.iter().zip(names) {
+            let samples: Vec<(&String, f64)> = self
+                .row_order
+                .iter()
+                .filter_map(|row| {
+                    let value = self.value(row, col)?.trim().parse::<f64>().ok()?;
+                    value.is_finite().then_some((row, value))
+                })
+                .collect();
+            if samples.is_empty() {
+                continue;
+            }
+
+            out.push_str(&format!("# TYPE {name} gauge\n"));
+            for (row, value) in samples {
+                out.push_str(&format!("{name}{{row=\"{}\"}} {value}\n", label_value(row)));
+            }
+        }
+        out
+    }
+}
+
+/// A `fmt::Write` sink that only counts the bytes written to it.
+struct ByteCounter(usize);
+
+impl fmt::Write for ByteCounter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Terminating marker written after the dataview by [`send_tcp`].
+#[cfg(feature = "net")]
+pub const TCP_END_MARKER: &str = "\n.\n";
+
+/// Terminating marker written by [`send_tcp`] after a dataview rendered with
+/// [`LineEnding::Crlf`].
+#[cfg(feature = "net")]
+pub const TCP_END_MARKER_CRLF: &str = "\r\n.\r\n";
+
+/// Sends the rendered dataview to a collector listening on `addr`.
+///
+/// Connects, writes the dataview as rendered by [`Dataview::write_to`],
+/// writes [`TCP_END_MARKER`] (a newline, a line holding a single `.`, and a
+/// final newline), or [`TCP_END_MARKER_CRLF`] if the view renders with
+/// [`LineEnding::Crlf`], and closes the connection.
+///
+/// A rendered line is only a lone `.` when a row (or the row header) is
+/// named `.` and every column is hidden by
+/// [`hide_empty_columns`](RenderOptions::hide_empty_columns). The collector
+/// would take that line for the end marker, so such a view is rejected with
+/// `io::ErrorKind::InvalidInput` before connecting.
+#[cfg(feature = "net")]
+pub fn send_tcp(view: &Dataview, addr: &str) -> io::Result<()> {
+    use std::io::Write;
+
+    let mut rendered = Vec::new();
+    view.write_to(&mut rendered)?;
+    let has_dot_line = rendered
+        .split(|&byte| byte == b'\n')
+        .any(|line| line.strip_suffix(b"\r").unwrap_or(line) == b".");
+    if has_dot_line {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "dataview renders a '.' line, which would end the transfer early",
+        ));
+    }
+
+    let mut stream = io::BufWriter::new(std::net::TcpStream::connect(addr)?);
+    stream.write_all(&rendered)?;
+    let marker = match view.render_options.line_ending {
+        LineEnding::Lf => TCP_END_MARKER,
+        LineEnding::Crlf => TCP_END_MARKER_CRLF,
+    };
+    stream.write_all(marker.as_bytes())?;
+    stream.flush()
+}
+
+/// Syslog facility used by [`log_to_syslog`].
+#[cfg(feature = "syslog")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SyslogFacility {
+    User = 1,
+    Daemon = 3,
+    Local0 = 16,
+    Local1 = 17,
+    Local2 = 18,
+    Local3 = 19,
+    Local4 = 20,
+    Local5 = 21,
+    Local6 = 22,
+    Local7 = 23,
+}
+
+/// Syslog severity level used by [`log_to_syslog`].
+#[cfg(feature = "syslog")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum SyslogLevel {
+    Emergency = 0,
+    Alert = 1,
+    Critical = 2,
+    Error = 3,
+    Warning = 4,
+    Notice = 5,
+    Info = 6,
+    Debug = 7,
+}
+
+/// Local socket [`log_to_syslog`] sends to.
+#[cfg(all(feature = "syslog", unix))]
+pub const SYSLOG_SOCKET: &str = "/dev/log";
+
+/// Returns the compact single-line summary [`log_to_syslog`] sends.
+///
+/// The line is the row header, the row count and each headline as
+/// `name=value`, in order. Values that are empty or contain whitespace,
+/// control characters, `"` or `=` are quoted, with `"` and `\` escaped by a
+/// backslash, `\n`, `\r` and `\t` written as escapes and other control
+/// characters as `\xNN`, so the line never breaks.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::prelude::*;
+/// use geneos_toolkit::dataview::syslog_line;
+/// let view = Dataview::builder()
+///     .set_row_header("host")
+///     .add_headline("region", "eu west")
+///     .add_value("web-01", "status", "up")
+///     .build()
+///     .unwrap();
+/// assert_eq!(syslog_line(&view), r#"host rows=1 region="eu west""#);
+/// ```
+#[cfg(feature = "syslog")]
+pub fn syslog_line(view: &Dataview) -> String {
+    fn field(s: &str) -> String {
+        if s.is_empty()
+            || s.chars()
+                .any(|c| c.is_whitespace() || c.is_control() || c == '"' || c == '=')
+        {
+            let mut quoted = String::with_capacity(s.len() + 2);
+            quoted.push('"');
+            for c in s.chars() {
+                match c {
+                    '"' => quoted.push_str("\\\""),
+                    '\\' => quoted.push_str("\\\\"),
+                    '\n' => quoted.push_str("\\n"),
+                    '\r' => quoted.push_str("\\r"),
+                    '\t' => quoted.push_str("\\t"),
+                    c if c.is_control() => quoted.push_str(&format!("\\x{:02x}", c as u32)),
+                    c => quoted.push(c),
+                }
+            }
+            quoted.push('"');
+            quoted
+        } else {
+            s.to_string()
+        }
+    }
+
+    let mut line = format!("{} rows={}", field(&view.row_header), view.row_order.len());
+    for key in &view.headline_order {
+        let value = view.headlines.get(key).map(String::as_str).unwrap_or("");
+        line.push_str(&format!(" {}={}", field(key), field(value)));
+    }
+    line
+}
+
+/// Logs a compact summary of the view to the local syslog daemon, as a
+/// fallback when the gateway cannot be reached.
+///
+/// Sends [`syslog_line`] as one datagram to [`SYSLOG_SOCKET`], tagged with
+/// the program name.
+#[cfg(all(feature = "syslog", unix))]
+pub fn log_to_syslog(
+    view: &Dataview,
+    facility: SyslogFacility,
+    level: SyslogLevel,
+) -> io::Result<()> {
+    let tag = std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "geneos-toolkit".to_string());
+    let priority = facility as u8 * 8 + level as u8;
+    let message = format!("<{priority}>{tag}: {}", syslog_line(view));
+
+    let socket = std::os::unix::net::UnixDatagram::unbound()?;
+    socket.send_to(message.as_bytes(), SYSLOG_SOCKET)?;
+    Ok(())
+}
+
+impl Dataview {
+    /// Creates a new DataviewBuilder instance
+    ///
+    /// This allows users to create a Dataview without explicitly importing DataviewBuilder
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let dataview = Dataview::builder()
+    ///     .set_row_header("ID")
+    ///     .add_headline("Total", "42")
+    ///     .add_value("1", "Name", "Alice")
+    ///     .build();
+    /// ```
+    pub fn builder() -> DataviewBuilder {
+        DataviewBuilder::new()
+    }
+}
+
+/// Column written by [`DataviewBuilder::style_rows`].
+pub const ROW_SEVERITY_COLUMN: &str = "severity";
+
+/// Prefix of the headline keys that carry column types, see
+/// [`DataviewBuilder::set_column_type`].
+pub const COLUMN_TYPE_HEADLINE_PREFIX: &str = "columnType.";
+
+/// The kind of data held by a column, emitted as metadata by
+/// [`DataviewBuilder::set_column_type`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ColumnType {
+    String,
+    Integer,
+    Float,
+    Percentage,
+}
+
+impl ColumnType {
+    /// Returns the lower-case name written into the type headline.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ColumnType::String => "string",
+            ColumnType::Integer => "integer",
+            ColumnType::Float => "float",
+            ColumnType::Percentage => "percentage",
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A reduction over the numeric cells of a column, used by
+/// [`DataviewBuilder::add_aggregate_headline`].
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Aggregate {
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Count,
+}
+
+impl Aggregate {
+    /// Applies the aggregate to `numbers`. Returns `None` for an empty input,
+    /// except for `Count` which returns `0`.
+    fn apply(&self, numbers: &[f64]) -> Option<f64> {
+        if numbers.is_empty() && *self != Aggregate::Count {
+            return None;
+        }
+        Some(match self {
+            Aggregate::Sum => numbers.iter().sum(),
+            Aggregate::Avg => numbers.iter().sum::<f64>() / numbers.len() as f64,
+            Aggregate::Min => numbers.iter().copied().fold(f64::INFINITY, f64::min),
+            Aggregate::Max => numbers.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            Aggregate::Count => numbers.len() as f64,
+        })
+    }
+}
+
+/// A helper struct to build a row of data.
+///
+/// This allows constructing a row with multiple columns before adding it to the Dataview.
+#[derive(Debug, Clone, Default)]
+pub struct Row {
+    name: String,
+    cells: Vec<(String, String)>,
+}
+
+impl Row {
+    /// Creates a new Row with the given name (row identifier).
+    pub fn new(name: impl ToString) -> Self {
+        Self {
+            name: name.to_string(),
+            cells: Vec::new(),
+        }
+    }
+
+    /// Creates a row with a cell for each of `columns` for which `f` returns
+    /// `Some`, in the order given. Columns for which `f` returns `None` are
+    /// left out.
+    ///
+    /// The row-level counterpart of [`DataviewBuilder::fill_cells`].
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let row = Row::from_columns("cpu0", &["user", "system"], |col| {
+    ///     Some(format!("{col}-value"))
+    /// });
+    /// assert_eq!(row.len(), 2);
+    /// ```
+    pub fn from_columns<F>(name: impl ToString, columns: &[&str], mut f: F) -> Self
+    where
+        F: FnMut(&str) -> Option<String>,
+    {
+        let cells = columns
+            .iter()
+            .filter_map(|column| Some((column.to_string(), f(column)?)))
+            .collect();
+        Self {
+            name: name.to_string(),
+            cells,
+        }
+    }
+
+    /// Adds a cell (column and value) to the row, preserving insertion order.
+    pub fn add_cell(mut self, column: impl ToString, value: impl ToString) -> Self {
+        self.cells.push((column.to_string(), value.to_string()));
+        self
+    }
+
+    /// Returns the row name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the number of cells added to the row.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Returns `true` if no cells have been added to the row.
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+}
+
+/// Converts a value into a dataview [`Row`], so a slice of them can be added
+/// with [`DataviewBuilder::add_rows`].
+///
+/// With the `derive` feature, `#[derive(ToRow)]` implements this for a
+/// struct: the row name comes from the field marked `#[torow(name)]` (or the
+/// first field), and every other field becomes a cell named after the field.
+/// Fields can be left out with `#[torow(skip)]` or given another column name
+/// with `#[torow(rename = "...")]`.
+pub trait ToRow {
+    /// Returns the row for this value.
+    fn to_row(&self) -> Row;
+}
+
+#[cfg(feature = "derive")]
+pub use geneos_toolkit_derive::ToRow;
+
+type RowStyleFn = dyn Fn(&str, &Dataview) -> Severity + Send + Sync;
+
+/// Rule set with [`DataviewBuilder::style_rows`], run once the view is built.
+#[derive(Clone)]
+struct RowStyler(Arc<RowStyleFn>);
+
+impl fmt::Debug for RowStyler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RowStyler")
+    }
+}
+
+/// A Builder for the `Dataview` struct.
+#[derive(Debug, Clone)]
+pub struct DataviewBuilder {
+    row_header: Option<String>,
+    default_row_header: Option<String>,
+    headlines: Option<HashMap<String, String>>,
+    values: Option<HashMap<(String, String), String>>,
+    headline_order: Vec<String>, // for the purpose of ordering the headlines
+    column_order: Vec<String>,   // for the purpose of ordering the columns
+    row_order: Vec<String>,      // for the purpose of ordering the rows
+    column_defaults: HashMap<String, String>,
+    strip_unicode: bool,
+    trim_values: bool,
+    case_insensitive_columns: bool,
+    require_headline: bool,
+    canonicalize: bool,
+    dedup_rows: bool,
+    group_separator: String,
+    aggregates: Vec<(String, String, Aggregate)>,
+    row_count_headline: Option<String>,
+    #[cfg(feature = "checksum")]
+    checksum_headline: Option<String>,
+    row_styler: Option<(String, RowStyler)>,
+    max_bytes: Option<usize>,
+    budget_mode: BudgetMode,
+    metadata: BTreeMap<String, String>,
+    render_options: RenderOptions,
+    header_rows: Vec<Row>,
+    footer_rows: Vec<Row>,
+}
+
+impl Default for DataviewBuilder {
+    fn default() -> Self {
+        Self {
+            row_header: None,
+            default_row_header: None,
+            headlines: None,
+            values: None,
+            headline_order: Vec::new(),
+            column_order: Vec::new(),
+            row_order: Vec::new(),
+            column_defaults: HashMap::new(),
+            strip_unicode: true,
+            trim_values: false,
+            case_insensitive_columns: false,
+            require_headline: false,
+            canonicalize: false,
+            dedup_rows: false,
+            group_separator: ".".to_string(),
+            aggregates: Vec::new(),
+            row_count_headline: None,
+            #[cfg(feature = "checksum")]
+            checksum_headline: None,
+            row_styler: None,
+            max_bytes: None,
+            budget_mode: BudgetMode::default(),
+            metadata: BTreeMap::new(),
+            render_options: RenderOptions::default(),
+            header_rows: Vec::new(),
+            footer_rows: Vec::new(),
+        }
+    }
+}
+
+impl DataviewBuilder {
+    /// Creates a new, empty builder.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a builder seeded with the contents of an existing view.
+    ///
+    /// The row header, headlines, columns, rows, cells and column defaults
+    /// are restored in their original order, so more values can be added and
+    /// the view rebuilt. New values are cleaned like the view's own, keeping
+    /// its `strip_unicode_controls` and `trim_values` settings. Headlines computed at build time, such as aggregates,
+    /// are restored as plain values.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::DataviewBuilder;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// let view = DataviewBuilder::from_dataview(&view)
+    ///     .add_value("web-02", "status", "down")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,status\nweb-01,up\nweb-02,down");
+    /// ```
+    pub fn from_dataview(view: &Dataview) -> Self {
+        Self {
+            row_header: Some(view.row_header.clone()),
+            headlines: Some(view.headlines.clone()),
+            values: Some(view.values.clone()),
+            headline_order: view.headline_order.clone(),
+            column_order: view.column_order.clone(),
+            row_order: view.row_order.clone(),
+            column_defaults: view.column_defaults.clone(),
+            render_options: view.render_options.clone(),
+            metadata: view.metadata.clone(),
+            strip_unicode: view.sanitizer.strip_unicode,
+            trim_values: view.sanitizer.trim_values,
+            ..Self::default()
+        }
+    }
+
+    /// Controls whether Unicode control characters (categories Cc and Cf,
+    /// excluding ASCII whitespace) are stripped from all input strings.
+    /// Enabled by default. Set to `false` to preserve raw Unicode control characters.
+    pub fn strip_unicode_controls(mut self, strip: bool) -> Self {
+        self.strip_unicode = strip;
+        self
+    }
+
+    /// Trims leading and trailing ASCII whitespace from every cell value at
+    /// build time. Opt-in; by default whitespace is preserved.
+    pub fn trim_values(mut self) -> Self {
+        self.trim_values = true;
+        self
+    }
+
+    /// Makes `build` fail with `DataviewError::DuplicateColumn` if two column
+    /// names differ only by case, since Geneos treats them as the same column.
+    /// Opt-in; by default column names are case-sensitive.
+    pub fn case_insensitive_columns(mut self) -> Self {
+        self.case_insensitive_columns = true;
+        self
+    }
+
+    /// Makes `build` fail with `DataviewError::MissingHeadline` if no headline
+    /// was added. Opt-in; by default headlines are optional.
+    pub fn require_headline(mut self) -> Self {
+        self.require_headline = true;
+        self
+    }
+
+    /// Sets the options the built view renders with, through `Display` and
+    /// [`Dataview::write_to`]. One `RenderOptions` can be shared by many views.
+    ///
+    /// The row header alias is sanitized like the row header, and `build`
+    /// fails with `DataviewError::EmptyName` if it is empty and with
+    /// `DataviewError::RowHeaderCollision` if it equals a column name.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::{LineEnding, RenderOptions};
+    /// let view = Dataview::builder()
+    ///     .set_row_header("id")
+    ///     .render_options(RenderOptions {
+    ///         line_ending: LineEnding::Crlf,
+    ///         ..RenderOptions::default()
+    ///     })
+    ///     .add_value("r1", "c1", "v1")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "id,c1\r\nr1,v1");
+    /// ```
+    pub fn render_options(mut self, mut options: RenderOptions) -> Self {
+        options.row_header_alias = options.row_header_alias.map(|alias| self.sanitize(&alias));
+        self.render_options = options;
+        self
+    }
+
+    /// Limits the rendered view, as measured by [`Dataview::rendered_len`],
+    /// to `limit` bytes. What happens to a larger view depends on the
+    /// [`budget_mode`](Self::budget_mode); by default `build` fails with
+    /// `DataviewError::TooLarge`.
+    pub fn max_bytes(mut self, limit: usize) -> Self {
+        self.max_bytes = Some(limit);
+        self
+    }
+
+    /// Sets what `build` does when the view is over [`max_bytes`](Self::max_bytes).
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::BudgetMode;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "status", "up")
+    ///     .add_value("web-02", "status", "up")
+    ///     .add_value("web-03", "status", "up")
+    ///     .max_bytes(40)
+    ///     .budget_mode(BudgetMode::Truncate)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,status\n<!>truncatedRows,2\nweb-01,up");
+    /// ```
+    pub fn budget_mode(mut self, mode: BudgetMode) -> Self {
+        self.budget_mode = mode;
+        self
+    }
+
+    /// Sanitize a string according to builder settings.
+    fn sanitize(&self, s: &str) -> String {
+        if self.strip_unicode {
+            strip_unicode_controls(s)
+        } else {
+            s.to_string()
+        }
+    }
+
+    /// Like `sanitize`, but reuses the allocation when nothing is stripped.
+    fn sanitize_owned(&self, s: String) -> String {
+        if self.strip_unicode && s.chars().any(is_stripped_control) {
+            strip_unicode_controls(&s)
+        } else {
+            s
+        }
+    }
+
+    /// Sets the mandatory row header label.
+    pub fn set_row_header(mut self, row_header: &str) -> Self {
+        self.row_header = Some(self.sanitize(row_header));
+        self
+    }
+
+    /// Sets a row header to use only if [`set_row_header`](Self::set_row_header)
+    /// is never called, so quick scripts can skip it without `build` failing
+    /// with `DataviewError::MissingRowHeader`. An explicit row header always
+    /// wins, whichever order the two are called in.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .with_default_row_header("name")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.row_header(), "name");
+    /// ```
+    pub fn with_default_row_header(mut self, row_header: &str) -> Self {
+        self.default_row_header = Some(self.sanitize(row_header));
+        self
+    }
+
+    /// Adds or replaces a headline value. Order is preserved by first insert.
+    pub fn add_headline<T: ToString>(mut self, key: &str, value: T) -> Self {
+        let key_string = self.sanitize(key);
+        let value_string = self.sanitize(&value.to_string());
+
+        let mut headlines: HashMap<String, String> = self.headlines.unwrap_or_default();
+
+        if !self.headline_order.contains(&key_string) {
+            self.headline_order.push(key_string.clone());
+        }
+
+        headlines.insert(key_string, value_string);
+        self.headlines = Some(headlines);
+        self
+    }
+
+    /// Adds or replaces each `(key, value)` headline in iteration order, like
+    /// calling [`add_headline`](Self::add_headline) for each pair.
+    ///
+    /// Order is preserved for ordered sources such as a `Vec` or `BTreeMap`.
+    /// A `HashMap` iterates in an unspecified order that can change between
+    /// runs, so its headlines may appear in a different order each time.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_headlines([("region", "eu"), ("status", "ok")])
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.headline_order(), &["region", "status"]);
+    /// ```
+    pub fn add_headlines<I, K, V>(mut self, headlines: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: ToString,
+        V: ToString,
+    {
+        for (key, value) in headlines {
+            self = self.add_headline(&key.to_string(), value);
+        }
+        self
+    }
+
+    /// Attaches a metadata entry, such as the source file or collection
+    /// time, that travels with the view for logging but is never rendered.
+    /// A later entry with the same key replaces an earlier one.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_metadata("source", "/var/log/app.log")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.metadata()["source"], "/var/log/app.log");
+    /// assert_eq!(view.to_string(), "host,status\nweb-01,up");
+    /// ```
+    pub fn add_metadata<T: ToString>(mut self, key: &str, value: T) -> Self {
+        self.metadata.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Declares columns up front, fixing their display order.
+    ///
+    /// Later `add_value` calls fill these columns in place; only columns not
+    /// declared here are appended after them. Declared columns that never
+    /// receive a value render as empty.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .set_columns(&["status", "cpu"])
+    ///     .add_value("web-01", "cpu", "10%")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,status,cpu\nweb-01,up,10%");
+    /// ```
+    pub fn set_columns(mut self, columns: &[&str]) -> Self {
+        for column in columns {
+            let column_string = self.sanitize(column);
+            if !self.column_order.contains(&column_string) {
+                self.column_order.push(column_string);
+            }
+        }
+        self
+    }
+
+    /// Sets a placeholder rendered for missing cells in `column`.
+    ///
+    /// The default is applied only when rendering: the stored data stays
+    /// sparse and `Dataview::value` still returns `None` for missing cells.
+    /// Columns without a default render missing cells as empty.
+    pub fn set_column_default<T: ToString>(mut self, column: &str, default: T) -> Self {
+        let column_string = self.sanitize(column);
+        let default_string = self.sanitize(&default.to_string());
+        self.column_defaults.insert(column_string, default_string);
+        self
+    }
+
+    /// Declares the type of `column` so the gateway can sort and format it.
+    ///
+    /// The toolkit format has no column metadata, so the type is emitted as a
+    /// headline named [`COLUMN_TYPE_HEADLINE_PREFIX`] followed by the column
+    /// name, e.g. `<!>columnType.cpu,float`. Setting a type again replaces it
+    /// in place.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::ColumnType;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .set_column_type("cpu", ColumnType::Percentage)
+    ///     .add_value("web-01", "cpu", "12")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,cpu\n<!>columnType.cpu,percentage\nweb-01,12");
+    /// ```
+    pub fn set_column_type(self, column: &str, column_type: ColumnType) -> Self {
+        let key = format!("{COLUMN_TYPE_HEADLINE_PREFIX}{column}");
+        self.add_headline(&key, column_type)
+    }
+
+    /// Adds a headline whose value is computed at build time by applying
+    /// `aggregate` to the numeric cells of `column`.
+    ///
+    /// Cells are parsed as `f64` after trimming; non-numeric cells, and cells
+    /// such as `NaN` or `inf` that parse to a non-finite number, are
+    /// ignored. If no cell is numeric the headline is empty (`Count` gives `0`).
+    /// The headline keeps its position from this call.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::Aggregate;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_aggregate_headline("totalUsedGb", "usedGb", Aggregate::Sum)
+    ///     .add_value("sda", "usedGb", "40")
+    ///     .add_value("sdb", "usedGb", "2.5")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.headline("totalUsedGb"), Some(&"42.5".to_string()));
+    /// ```
+    pub fn add_aggregate_headline(mut self, key: &str, column: &str, aggregate: Aggregate) -> Self {
+        let key_string = self.sanitize(key);
+        let column_string = self.sanitize(column);
+        self.aggregates
+            .push((key_string.clone(), column_string, aggregate));
+        self.add_headline(&key_string, "")
+    }
+
+    /// Adds a headline named `key` holding the number of rows in the built
+    /// dataview, computed at build time after deduplication and any
+    /// [`BudgetMode::Truncate`] truncation. Header and footer rows are not
+    /// counted, so it is `row_order().len()` minus the pinned rows.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_row_count_headline("RowCount")
+    ///     .add_value("web-01", "status", "up")
+    ///     .add_value("web-02", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.headline("RowCount"), Some(&"2".to_string()));
+    /// ```
+    pub fn add_row_count_headline(mut self, key: &str) -> Self {
+        let key_string = self.sanitize(key);
+        self.row_count_headline = Some(key_string.clone());
+        self.add_headline(&key_string, "")
+    }
+
+    /// Adds a headline named `key` holding a checksum of the built dataview's
+    /// content, computed at build time.
+    ///
+    /// The checksum covers the row header, the other headlines, the columns
+    /// and every cell (missing cells as their column default), all in display
+    /// order. It is a SHA-256 hash, computed with the `sha2` crate, written as
+    /// 64 lower-case hex digits, so identical content always gives the same
+    /// value across runs and platforms. It is not keyed, so it only proves
+    /// integrity when the checksum itself comes from a trusted source.
+    ///
+    /// Requires the `checksum` feature.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let build = |status: &str| {
+    ///     Dataview::builder()
+    ///         .set_row_header("host")
+    ///         .add_checksum_headline("checksum")
+    ///         .add_value("web-01", "status", status)
+    ///         .build()
+    ///         .unwrap()
+    /// };
+    /// assert_eq!(build("up").headline("checksum"), build("up").headline("checksum"));
+    /// assert_ne!(build("up").headline("checksum"), build("down").headline("checksum"));
+    /// ```
+    #[cfg(feature = "checksum")]
+    pub fn add_checksum_headline(mut self, key: &str) -> Self {
+        let key_string = self.sanitize(key);
+        self.checksum_headline = Some(key_string.clone());
+        self.add_headline(&key_string, "")
+    }
+
+    /// Maps the view to the managed entity `name` by adding a
+    /// [`MANAGED_ENTITY_HEADLINE`] (`_entity`) headline, the key the gateway
+    /// reads for entity mapping. Calling it again replaces the entity.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .set_managed_entity("web-tier")
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,status\n<!>_entity,web-tier\nweb-01,up");
+    /// ```
+    pub fn set_managed_entity(self, name: &str) -> Self {
+        self.add_headline(MANAGED_ENTITY_HEADLINE, name)
+    }
+
+    /// Adds a headline named `key` holding how often the sampler runs.
+    ///
+    /// The period is written as whole seconds (fractions are truncated) rather
+    /// than an ISO-8601 duration, so the gateway can treat it as a number.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use std::time::Duration;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_sample_period_headline("samplePeriod", Duration::from_secs(30))
+    ///     .add_value("web-01", "status", "up")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.headline("samplePeriod"), Some(&"30".to_string()));
+    /// ```
+    pub fn add_sample_period_headline(self, key: &str, period: Duration) -> Self {
+        self.add_headline(key, period.as_secs())
+    }
+
+    /// Adds a single cell value at `row`/`column`, recording insertion order.
+    pub fn add_value<T: ToString>(self, row: &str, column: &str, value: T) -> Self {
+        self.add_value_owned(row, column, value.to_string())
+    }
+
+    /// Adds a single cell value like `add_value`, taking ownership of `value`.
+    ///
+    /// The `String` is stored as-is rather than copied, unless control
+    /// characters have to be stripped from it.
+    pub fn add_value_owned(mut self, row: &str, column: &str, value: String) -> Self {
+        let column_string = self.sanitize(column);
+        let row_string = self.sanitize(row);
+        let value_string = self.sanitize_owned(value);
+
+        let mut values: HashMap<(String, String), String> = self.values.unwrap_or_default();
+
+        // Track columns in order of insertion (if new)
+        if !self.column_order.contains(&column_string) {
+            self.column_order.push(column_string.clone());
+        }
+
+        // Track rows in order of insertion (if new)
+        if !self.row_order.contains(&row_string) {
+            self.row_order.push(row_string.clone());
+        }
+
+        values.insert((row_string, column_string), value_string);
+        self.values = Some(values);
+        self
+    }
+
+    /// Fills cells from `f(row, column)` for every row/column pair, row by row.
+    ///
+    /// A cell is set only when `f` returns `Some`; rows and columns that never
+    /// receive a value are not added.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("x")
+    ///     .fill_cells(&["1", "2"], &["1", "2"], |r, c| {
+    ///         let product: u32 = r.parse::<u32>().ok()? * c.parse::<u32>().ok()?;
+    ///         Some(product.to_string())
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "x,1,2\n1,1,2\n2,2,4");
+    /// ```
+    pub fn fill_cells<F>(mut self, rows: &[&str], columns: &[&str], mut f: F) -> Self
+    where
+        F: FnMut(&str, &str) -> Option<String>,
+    {
+        for row in rows {
+            for column in columns {
+                if let Some(value) = f(row, column) {
+                    self = self.add_value_owned(row, column, value);
+                }
+            }
+        }
+        self
+    }
+
+    /// Moves every cell of column `from` into column `into` and removes `from`.
+    ///
+    /// Cells already present in `into` are kept; use
+    /// [`merge_columns_overwrite`](Self::merge_columns_overwrite) to let `from`
+    /// win instead. `into` keeps its position; if it did not exist yet it takes
+    /// the position of `from`.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .add_value("web-02", "CPU%", "20")
+    ///     .merge_columns("cpu", "CPU%")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,cpu\nweb-01,10\nweb-02,20");
+    /// ```
+    pub fn merge_columns(self, into: &str, from: &str) -> Self {
+        self.merge_columns_impl(into, from, false)
+    }
+
+    /// Like [`merge_columns`](Self::merge_columns), but cells from `from`
+    /// replace existing cells in `into`.
+    pub fn merge_columns_overwrite(self, into: &str, from: &str) -> Self {
+        self.merge_columns_impl(into, from, true)
+    }
+
+    fn merge_columns_impl(mut self, into: &str, from: &str, overwrite: bool) -> Self {
+        let into = self.sanitize(into);
+        let from = self.sanitize(from);
+        if into == from {
+            return self;
+        }
+        let Some(from_pos) = self.column_order.iter().position(|c| *c == from) else {
+            return self;
+        };
+
+        if self.column_order.contains(&into) {
+            self.column_order.remove(from_pos);
+        } else {
+            self.column_order[from_pos] = into.clone();
+        }
+
+        if let Some(values) = self.values.as_mut() {
+            for row in &self.row_order {
+                if let Some(value) = values.remove(&(row.clone(), from.clone())) {
+                    let key = (row.clone(), into.clone());
+                    if overwrite || !values.contains_key(&key) {
+                        values.insert(key, value);
+                    }
+                }
+            }
+        }
+        self
+    }
+
+    /// Sets the separator placed between group and column names by
+    /// `add_grouped_values`. Defaults to `"."`.
+    pub fn set_group_separator(mut self, separator: &str) -> Self {
+        self.group_separator = separator.to_string();
+        self
+    }
+
+    /// Adds a group of related cells to `row`, naming each column
+    /// `{group}{separator}{column}`.
+    ///
+    /// Geneos has no column groups; prefixed names are the usual convention.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_grouped_values("web-01", "cpu", &[("user", "12%"), ("system", "3%")])
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.column_order(), &["cpu.user", "cpu.system"]);
+    /// ```
+    pub fn add_grouped_values(mut self, row: &str, group: &str, pairs: &[(&str, &str)]) -> Self {
+        for (column, value) in pairs {
+            let column = format!("{group}{}{column}", self.group_separator);
+            self = self.add_value(row, &column, value);
+        }
+        self
+    }
+
+    /// Adds a complete row to the Dataview.
+    ///
+    /// This is a convenience method to add multiple values for the same row at once.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    ///
+    /// let row = Row::new("process1")
+    ///     .add_cell("Status", "Running")
+    ///     .add_cell("CPU", "2.5%");
+    ///
+    /// let dataview = Dataview::builder()
+    ///     .set_row_header("Process")
+    ///     .add_row(row)
+    ///     .build();
+    /// ```
+    pub fn add_row(mut self, row: Row) -> Self {
+        for (col, val) in row.cells {
+            self = self.add_value_owned(&row.name, &col, val);
+        }
+        self
+    }
+
+    /// Adds one row per item, in order, using [`ToRow`].
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// use geneos_toolkit::dataview::ToRow;
+    ///
+    /// struct Disk {
+    ///     name: String,
+    ///     used: u32,
+    /// }
+    ///
+    /// impl ToRow for Disk {
+    ///     fn to_row(&self) -> Row {
+    ///         Row::new(&self.name).add_cell("used", self.used)
+    ///     }
+    /// }
+    ///
+    /// let disks = [Disk { name: "sda".into(), used: 30 }];
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_rows(&disks)
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "disk,used\nsda,30");
+    /// ```
+    pub fn add_rows<T: ToRow>(mut self, rows: &[T]) -> Self {
+        for row in rows {
+            self = self.add_row(row.to_row());
+        }
+        self
+    }
+
+    /// Adds a row to be placed before all other rows at build time, such as
+    /// an "Average" row. This is a data row, not the column header row.
+    ///
+    /// Header rows are placed after any row sorting, so they always end up
+    /// first, in the order they were added. Like footer rows, they are not
+    /// counted by [`add_row_count_headline`](Self::add_row_count_headline) or
+    /// [`add_aggregate_headline`](Self::add_aggregate_headline).
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_header_row(Row::new("Average").add_cell("used", "25"))
+    ///     .add_value("sdb", "used", "20")
+    ///     .add_value("sda", "used", "30")
+    ///     .sort_rows()
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.row_order(), &["Average", "sda", "sdb"]);
+    /// ```
+    pub fn add_header_row(mut self, row: Row) -> Self {
+        self.header_rows.push(row);
+        self
+    }
+
+    /// Adds a row to be appended after all other rows at build time, such as
+    /// a "Totals" row.
+    ///
+    /// Footer rows are added after any row sorting, so they always end up
+    /// last, in the order they were added. They are not counted by
+    /// [`add_row_count_headline`](Self::add_row_count_headline) or
+    /// [`add_aggregate_headline`](Self::add_aggregate_headline).
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("disk")
+    ///     .add_footer_row(Row::new("Totals").add_cell("used", "50"))
+    ///     .add_value("sdb", "used", "20")
+    ///     .add_value("sda", "used", "30")
+    ///     .sort_rows()
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.row_order(), &["sda", "sdb", "Totals"]);
+    /// ```
+    pub fn add_footer_row(mut self, row: Row) -> Self {
+        self.footer_rows.push(row);
+        self
+    }
+
+    /// Sorts rows in ascending order by row name. Opt-in; default is insertion order.
+    /// Sorts rows in ascending order by row name. Opt-in; default is insertion order.
+    pub fn sort_rows(mut self) -> Self {
+        self.row_order.sort();
+        self
+    }
+
+    /// Sorts columns in ascending order by name. Opt-in; default is insertion order.
+    pub fn sort_columns(mut self) -> Self {
+        self.column_order.sort();
+        self
+    }
+
+    /// Sorts headlines in ascending order by key. Opt-in; default is insertion order.
+    pub fn sort_headlines(mut self) -> Self {
+        self.headline_order.sort();
+        self
+    }
+
+    /// Removes rows whose cells are all identical to the row just before
+    /// them, at build time and in the final row order. Only exact adjacent
+    /// duplicates collapse, so the first of each run is kept; header and
+    /// footer rows are never removed. Row count and aggregate headlines count
+    /// only the rows kept. Opt-in; by default every row is kept.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("event")
+    ///     .add_value("e1", "message", "disk full")
+    ///     .add_value("e2", "message", "disk full")
+    ///     .add_value("e3", "message", "ok")
+    ///     .dedup_rows()
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.row_order(), &["e1", "e3"]);
+    /// ```
+    pub fn dedup_rows(mut self) -> Self {
+        self.dedup_rows = true;
+        self
+    }
+
+    /// Sorts rows, columns and headlines ascending by name, so the output
+    /// does not depend on insertion order. Useful for snapshot tests.
+    ///
+    /// This is [`sort_rows`](Self::sort_rows),
+    /// [`sort_columns`](Self::sort_columns) and
+    /// [`sort_headlines`](Self::sort_headlines) applied at build time, so it
+    /// also covers values added after it in the chain. Header and footer rows
+    /// keep their positions.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .canonicalize()
+    ///     .set_row_header("host")
+    ///     .add_value("web-02", "mem", "60")
+    ///     .add_value("web-01", "cpu", "10")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,cpu,mem\nweb-01,10,\nweb-02,,60");
+    /// ```
+    pub fn canonicalize(mut self) -> Self {
+        self.canonicalize = true;
+        self
+    }
+
+    /// Sorts rows using a key selector. Opt-in; default is insertion order.
+    pub fn sort_rows_by<K, F>(mut self, mut f: F) -> Self
+    where
+        K: Ord,
+        F: FnMut(&str) -> K,
+    {
+        self.row_order.sort_by_key(|row| f(row));
+        self
+    }
+
+    /// Sorts rows using a custom comparator. Opt-in; default is insertion order.
+    pub fn sort_rows_with<F>(mut self, mut cmp: F) -> Self
+    where
+        F: FnMut(&str, &str) -> std::cmp::Ordering,
+    {
+        self.row_order.sort_by(|a, b| cmp(a, b));
+        self
+    }
+
+    /// Computes a severity for each row from its data and writes it to the
+    /// [`ROW_SEVERITY_COLUMN`] column, where Gateway rules and
+    /// [`Dataview::row_max_severity`] can pick it up.
+    ///
+    /// Like the aggregates, `f` runs at build time, once per row, with the
+    /// row name and the built view, so rows added after this call are styled
+    /// too. `build` fails with `DataviewError::ColumnExists` if the view
+    /// already has a `severity` column; use [`Self::style_rows_into`] to
+    /// write the severity elsewhere.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("host")
+    ///     .add_value("web-01", "cpu", "95")
+    ///     .style_rows(|row, view| {
+    ///         match view.value(row, "cpu").and_then(|v| v.parse::<f64>().ok()) {
+    ///             Some(cpu) if cpu > 90.0 => Severity::Critical,
+    ///             Some(_) => Severity::Ok,
+    ///             None => Severity::Undefined,
+    ///         }
+    ///     })
+    ///     .add_value("web-02", "cpu", "20")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.to_string(), "host,cpu,severity\nweb-01,95,CRITICAL\nweb-02,20,OK");
+    /// ```
+    pub fn style_rows<F>(self, f: F) -> Self
+    where
+        F: Fn(&str, &Dataview) -> Severity + Send + Sync + 'static,
+    {
+        self.style_rows_into(ROW_SEVERITY_COLUMN, f)
+    }
+
+    /// Like [`Self::style_rows`], but writes the severity to `column`.
+    pub fn style_rows_into<F>(mut self, column: &str, f: F) -> Self
+    where
+        F: Fn(&str, &Dataview) -> Severity + Send + Sync + 'static,
+    {
+        let column = self.sanitize(column);
+        self.row_styler = Some((column, RowStyler(Arc::new(f))));
+        self
+    }
+
+    /// Writes each row's worst cell severity, as found by
+    /// [`Dataview::row_max_severity`], to `column` at build time. Rows with
+    /// no severity cell get `UNDEFINED`.
+    ///
+    /// This is a row styler, so it replaces any earlier [`Self::style_rows`]
+    /// call and is replaced by a later one.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let view = Dataview::builder()
+    ///     .set_row_header("check")
+    ///     .add_value("disk", "space", "OK")
+    ///     .add_value("disk", "inodes", "CRITICAL")
+    ///     .propagate_row_severity("status")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(view.value("disk", "status").map(String::as_str), Some("CRITICAL"));
+    /// ```
+    pub fn propagate_row_severity(self, column: &str) -> Self {
+        self.style_rows_into(column, |row, view| {
+            view.row_max_severity(row).unwrap_or(Severity::Undefined)
+        })
+    }
+
+    /// Returns `true` if a row header has been set.
+    pub fn is_row_header_set(&self) -> bool {
+        self.row_header.is_some()
+    }
+
+    /// Returns the number of distinct rows added so far.
+    pub fn num_pending_rows(&self) -> usize {
+        self.row_order.len()
+    }
+
+    /// Returns the number of cells added so far.
+    pub fn num_pending_values(&self) -> usize {
+        self.values.as_ref().map_or(0, HashMap::len)
+    }
+
+    /// Renders the builder's current state for debugging, without building.
+    ///
+    /// The result is NOT valid toolkit output: it starts with a
+    /// `# PREVIEW` marker line, an unset row header is shown as `<unset>`
+    /// and no validation is performed.
+    ///
+    /// # Example
+    /// ```
+    /// use geneos_toolkit::prelude::*;
+    /// let builder = Dataview::builder().add_value("proc1", "Status", "Running");
+    /// assert_eq!(
+    ///     builder.preview(),
+    ///     "# PREVIEW (not valid toolkit output)\n<unset>,Status\nproc1,Running"
+    /// );
+    /// ```
+    pub fn preview(&self) -> String {
+        BuilderPreview(self).to_string()
+    }
+
+    /// Builds the `Dataview`, consuming the builder.
+    ///
+    /// The `row_header` must be set before the build or a panic will occur.
+    /// There must be at least one value.
+    /// Headlines are optional. A headline key may not start with the `<!>`
+    /// headline marker, since the output could not be parsed back unambiguously.
+    ///
+    /// The order of the columns and rows is determined by the order in which they are added through
+    /// values using the `add_value` method.
+    ///
+    /// The order of headlines is determined by the order in which they are added through the
+    /// `add_headline` method.
+    ///
+    /// Example:
+    /// ```rust
+    /// use geneos_toolkit::prelude::*;
+    ///
+    /// let view: Dataview = Dataview::builder()
+    ///     .set_row_header("Name")
+    ///     .add_headline("AverageAge", "30")
+    ///     .add_value("Anna", "Age", "30")
+    ///     .add_value("Bertil", "Age", "20")
+    ///     .add_value("Caesar", "Age", "40")
+    ///     .build()
+    ///     .unwrap();
+    ///
+    /// ```
+    pub fn build(mut self) -> Result<Dataview, DataviewError> {
+        let header_rows = std::mem::take(&mut self.header_rows);
+        let footer_rows = std::mem::take(&mut self.footer_rows);
+        for row in header_rows.iter().chain(&footer_rows) {
+            let name = self.sanitize(&row.name);
+            self.row_order.retain(|r| *r != name);
+        }
+        let body = std::mem::take(&mut self.row_order);
+        for row in header_rows {
+            self = self.add_row(row);
+        }
+        let body_rows = self.row_order.len()..self.row_order.len() + body.len();
+        self.row_order.extend(body);
+        for row in footer_rows {
+            self = self.add_row(row);
+        }
+        if self.canonicalize {
+            self.row_order[body_rows.clone()].sort();
+            self.column_order.sort();
+            self.headline_order.sort();
+        }
+
+        let row_header = self
+            .row_header
+            .or(self.default_row_header)
+            .ok_or(DataviewError::MissingRowHeader)?;
+
+        if row_header.is_empty() {
+            return Err(DataviewError::empty_name("row header", None));
+        }
+
+        let mut values = self.values.ok_or(DataviewError::MissingValue)?;
+
+        if self.require_headline && self.headline_order.is_empty() {
+            return Err(DataviewError::MissingHeadline);
+        }
+
+        for row in &self.row_order {
+            if row.is_empty() {
+                let column = self
+                    .column_order
+                    .iter()
+                    .find(|col| values.contains_key(&(row.clone(), (*col).clone())));
+                return Err(DataviewError::empty_name(
+                    "row",
+                    column.map(|col| format!("column '{col}'")),
+                ));
+            }
+        }
+
+        for col in &self.column_order {
+            if col.is_empty() {
+                let row = self
+                    .row_order
+                    .iter()
+                    .find(|row| values.contains_key(&((*row).clone(), col.clone())));
+                return Err(DataviewError::empty_name(
+                    "column",
+                    row.map(|row| format!("row '{row}'")),
+                ));
+            }
+        }
+
+        if self.case_insensitive_columns {
+            let mut seen: HashMap<String, &String> = HashMap::new();
+            for col in &self.column_order {
+                if let Some(first) = seen.insert(col.to_lowercase(), col) {
+                    return Err(DataviewError::DuplicateColumn(first.clone(), col.clone()));
+                }
+            }
+        }
+
+        if let Some(ref headlines) = self.headlines {
+            for key in headlines.keys() {
+                if key.is_empty() {
+                    return Err(DataviewError::empty_name("headline", None));
+                }
+            }
+        }
+
+        for key in &self.headline_order {
+            if key.starts_with("<!>") {
+                return Err(DataviewError::HeadlinePrefixInKey(key.clone()));
+            }
+        }
+
+        if self.trim_values {
+            for value in values.values_mut() {
+                let trimmed = value.trim_ascii();
+                if trimmed.len() != value.len() {
+                    *value = trimmed.to_string();
+                }
+            }
+        }
+
+        let mut body_rows = body_rows;
+        if self.dedup_rows {
+            let body: Vec<String> = self.row_order.drain(body_rows.clone()).collect();
+            let cells = |row: &String| -> Vec<Option<&String>> {
+                self.column_order
+                    .iter()
+                    .map(|col| values.get(&(row.clone(), col.clone())))
+                    .collect()
+            };
+            let mut kept: Vec<String> = Vec::with_capacity(body.len());
+            let mut dropped = HashSet::new();
+            let mut previous = None;
+            for row in body {
+                let current = cells(&row);
+                if previous.as_ref() == Some(&current) {
+                    dropped.insert(row);
+                } else {
+                    previous = Some(current);
+                    kept.push(row);
+                }
+            }
+            values.retain(|(row, _), _| !dropped.contains(row));
+            body_rows.end = body_rows.start + kept.len();
+            self.row_order
+                .splice(body_rows.start..body_rows.start, kept);
+        }
+
+        let computed = ComputedHeadlines {
+            aggregates: &self.aggregates,
+            row_count: self.row_count_headline.as_deref(),
+        };
+        let mut headlines = self.headlines.unwrap_or_default();
+        computed.apply(&mut headlines, &values, &self.row_order[body_rows.clone()]);
+
+        let mut view = Dataview {
+            row_header,
+            headlines,
+            headline_order: self.headline_order,
+            values,
+            column_order: self.column_order,
+            row_order: self.row_order,
+            column_defaults: self.column_defaults,
+            render_options: self.render_options,
+            metadata: self.metadata,
+            sanitizer: Sanitizer {
+                strip_unicode: self.strip_unicode,
+                trim_values: self.trim_values,
+            },
+        };
+        if let Some((column, styler)) = self.row_styler {
+            if column.is_empty() {
+                return Err(DataviewError::empty_name("column", None));
+            }
+            if column == view.row_header {
+                return Err(DataviewError::RowHeaderCollision(column));
+            }
+            let exists = view.column_order.iter().any(|col| {
+                *col == column
+                    || (self.case_insensitive_columns && col.eq_ignore_ascii_case(&column))
+            });
+            if exists {
+                return Err(DataviewError::ColumnExists(column));
+            }
+            let severities: Vec<(String, String)> = view
+                .row_order
+                .iter()
+                .map(|row| (row.clone(), (styler.0)(row, &view).to_string()))
+                .collect();
+            for (row, severity) in severities {
+                view.values.insert((row, column.clone()), severity);
+            }
+            view.column_order.push(column);
+            if self.canonicalize {
+                view.column_order.sort();
+            }
+        }
+        if let Some(alias) = &view.render_options.row_header_alias {
+            if alias.is_empty() {
+                return Err(DataviewError::empty_name("row header alias", None));
+            }
+            if view.column_order.contains(alias) {
+                return Err(DataviewError::RowHeaderCollision(alias.clone()));
+            }
+        }
+        #[cfg(feature = "checksum")]
+        if let Some(key) = &self.checksum_headline {
+            let checksum = content_checksum(&view, key);
+            view.headlines.insert(key.clone(), checksum);
+        }
+        if let Some(limit) = self.max_bytes {
+            if self.budget_mode == BudgetMode::Truncate
+                && view.headlines.contains_key(TRUNCATED_HEADLINE)
+            {
+                return Err(DataviewError::ReservedHeadline(
+                    TRUNCATED_HEADLINE.to_string(),
+                ));
+            }
+            let bytes = view.rendered_len();
+            if bytes > limit {
+                if self.budget_mode == BudgetMode::Fail {
+                    return Err(DataviewError::TooLarge { bytes, limit });
+                }
+                truncate_to_budget(&mut view, limit, body_rows, &computed)?;
+                #[cfg(feature = "checksum")]
+                if let Some(key) = self.checksum_headline {
+                    let checksum = content_checksum(&view, &key);
+                    view.headlines.insert(key, checksum);
+                }
+            }
+        }
+        Ok(view)
+    }
+}
+
+/// What [`DataviewBuilder::build`] does with a view over
+/// [`DataviewBuilder::max_bytes`].
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum BudgetMode {
+    /// Fail with `DataviewError::TooLarge`.
+    #[default]
+    Fail,
+    /// Drop data rows from the end until the view fits, and record how many
+    /// were dropped in a [`TRUNCATED_HEADLINE`] headline. Header and footer
+    /// rows are kept, and headlines computed at build time, such as
+    /// aggregates and the row count, are recomputed over the rows that are
+    /// left. Fails with
+    /// `DataviewError::TooLarge` if the view does not fit even with no data
+    /// rows, and with `DataviewError::ReservedHeadline` if the view already
+    /// has a [`TRUNCATED_HEADLINE`] headline.
+    Truncate,
+}
+
+/// Headline key holding the number of rows dropped by
+/// [`BudgetMode::Truncate`].
+pub const TRUNCATED_HEADLINE: &str = "truncatedRows";
+
+/// Headlines [`DataviewBuilder::build`] computes from the data rows.
+struct ComputedHeadlines<'a> {
+    aggregates: &'a [(String, String, Aggregate)],
+    row_count: Option<&'a str>,
+}
+
+impl ComputedHeadlines<'_> {
+    /// Sets each computed headline in `headlines` from the data `rows`.
+    fn apply(
+        &self,
+        headlines: &mut HashMap<String, String>,
+        values: &HashMap<(String, String), String>,
+        rows: &[String],
+    ) {
+        for (key, column, aggregate) in self.aggregates {
+            let numbers: Vec<f64> = rows
+                .iter()
+                .filter_map(|row| values.get(&(row.clone(), column.clone())))
+                .filter_map(|value| value.trim().parse::<f64>().ok())
+                .filter(|number| number.is_finite())
+                .collect();
+            let result = aggregate
+                .apply(&numbers)
+                .map(|n| n.to_string())
+                .unwrap_or_default();
+            headlines.insert(key.clone(), result);
+        }
+        if let Some(key) = self.row_count {
+            headlines.insert(key.to_string(), rows.len().to_string());
+        }
+    }
+}
+
+/// Drops rows from the end of `body_rows` for `view` to render within
+/// `limit` bytes, recomputing the `computed` headlines over the rows kept.
+///
+/// Dropping a row removes its whole line, which saves more bytes than the
+/// count headlines can grow by, so the cut point is binary-searched. An
+/// aggregate can get longer as rows are dropped (an average gaining
+/// decimals, say), so with aggregates the search may drop a row more than
+/// strictly needed; the result always fits.
+fn truncate_to_budget(
+    view: &mut Dataview,
+    limit: usize,
+    body_rows: std::ops::Range<usize>,
+    computed: &ComputedHeadlines,
+) -> Result<(), DataviewError> {
+    let footer = view.row_order.split_off(body_rows.end);
+    let body = view.row_order.split_off(body_rows.start);
+    let header_len = view.row_order.len();
+    view.headline_order.push(TRUNCATED_HEADLINE.to_string());
+    let render_with = |view: &mut Dataview, kept: usize| {
+        view.row_order.truncate(header_len);
+        view.row_order.extend(body[..kept].iter().cloned());
+        view.row_order.extend(footer.iter().cloned());
+        view.headlines.insert(
+            TRUNCATED_HEADLINE.to_string(),
+            (body.len() - kept).to_string(),
+        );
+        computed.apply(&mut view.headlines, &view.values, &body[..kept]);
+        view.rendered_len()
+    };
+
+    let bytes = render_with(view, 0);
+    if bytes > limit {
+        return Err(DataviewError::TooLarge { bytes, limit });
+    }
+    // `low` rows are known to fit and `high` rows known not to.
+    let (mut low, mut high) = (0, body.len());
+    while high - low > 1 {
+        let mid = low + (high - low) / 2;
+        if render_with(view, mid) <= limit {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    render_with(view, low);
+
+    let dropped: HashSet<&String> = body[low..].iter().collect();
+    view.values.retain(|(row, _), _| !dropped.contains(row));
+    Ok(())
+}
+
+/// The fixed parts of a dataview that stay the same across sampling cycles.
+///
+/// A template holds the row header, fixed columns, static headlines and
+/// render options. Call [`DataviewTemplate::new_builder`] once per cycle to get
+/// a builder that already has them, then add that cycle's rows.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::dataview::DataviewTemplate;
+///
+/// let template = DataviewTemplate::new("host")
+///     .set_columns(&["status", "cpu"])
+///     .add_headline("region", "eu");
+///
+/// let view = template
+///     .new_builder()
+///     .add_value("web-01", "cpu", "42")
+///     .build()
+///     .unwrap();
+/// assert_eq!(view.to_string(), "host,status,cpu\n<!>region,eu\nweb-01,,42");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DataviewTemplate {
+    row_header: String,
+    columns: Vec<String>,
+    headlines: Vec<(String, String)>,
+    render_options: RenderOptions,
+}
+
+impl DataviewTemplate {
+    /// Creates a template with the given row header.
+    pub fn new(row_header: &str) -> Self {
+        Self {
+            row_header: row_header.to_string(),
+            ..Self::default()
+        }
+    }
+
+    /// Adds fixed columns, in order, like [`DataviewBuilder::set_columns`].
+    pub fn set_columns(mut self, columns: &[&str]) -> Self {
+        self.columns
+            .extend(columns.iter().map(|column| column.to_string()));
+        self
+    }
+
+    /// Adds a static headline shown in every cycle.
+    pub fn add_headline<T: ToString>(mut self, key: &str, value: T) -> Self {
+        self.headlines.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the options each cycle's view renders with.
+    pub fn set_render_options(mut self, options: RenderOptions) -> Self {
+        self.render_options = options;
+        self
+    }
+
+    /// Returns the render options for each cycle's view.
+    pub fn render_options(&self) -> RenderOptions {
+        self.render_options.clone()
+    }
+
+    /// Returns a builder pre-populated with the row header, fixed columns,
+    /// static headlines and render options, ready for one cycle's rows.
+    pub fn new_builder(&self) -> DataviewBuilder {
+        let columns: Vec<&str> = self.columns.iter().map(String::as_str).collect();
+        let mut builder = DataviewBuilder::new()
+            .set_row_header(&self.row_header)
+            .set_columns(&columns)
+            .render_options(self.render_options.clone());
+        for (key, value) in &self.headlines {
+            builder = builder.add_headline(key, value);
+        }
+        builder
+    }
+}
+
+/// Parses a number followed by an optional unit, e.g. `97%` or `150 MB`.
+fn parse_with_unit(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .trim_end_matches(|c: char| c.is_alphabetic() || c == '%')
+        .trim_end()
+        .parse()
+        .ok()
+}
+
+/// SHA-256 of the view's content, skipping the `exclude` headline, as 64
+/// lower-case hex digits.
+///
+/// Every field is length-prefixed so that moving a delimiter between fields
+/// always changes the hash.
+#[cfg(feature = "checksum")]
+fn content_checksum(view: &Dataview, exclude: &str) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hash = Sha256::new();
+    let mut field = |field: &str| {
+        hash.update((field.len() as u64).to_le_bytes());
+        hash.update(field.as_bytes());
+    };
+    field(&view.row_header);
+    for key in view.headline_order.iter().filter(|key| *key != exclude) {
+        field(key);
+        field(view.headlines.get(key).map(String::as_str).unwrap_or(""));
+    }
+    for col in &view.column_order {
+        field(col);
+    }
+    for row in &view.row_order {
+        field(row);
+        for col in &view.column_order {
+            let value = view
+                .value(row, col)
+                .or_else(|| view.column_defaults.get(col))
+                .map(String::as_str)
+                .unwrap_or("");
+            field(value);
+        }
+    }
+    hash.finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Lists the rows whose severity changed between two snapshots, for
+/// notifying only on real changes.
+///
+/// Each entry is `(row, old, new)`, with each severity computed by
+/// [`Dataview::row_max_severity`]. Rows added in `new` appear as `None` to
+/// `Some` and rows removed from `old` as `Some` to `None`; rows without a
+/// severity in either snapshot are not listed. Rows are listed in `new`'s
+/// order, followed by removed rows in `old`'s order.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::prelude::*;
+/// use geneos_toolkit::dataview::severity_transitions;
+/// let old = Dataview::builder()
+///     .set_row_header("check")
+///     .add_value("disk", "status", "OK")
+///     .build()
+///     .unwrap();
+/// let new = Dataview::builder()
+///     .set_row_header("check")
+///     .add_value("disk", "status", "WARNING")
+///     .build()
+///     .unwrap();
+/// assert_eq!(
+///     severity_transitions(&old, &new),
+///     vec![("disk".to_string(), Some(Severity::Ok), Some(Severity::Warning))]
+/// );
+/// ```
+pub fn severity_transitions(
+    old: &Dataview,
+    new: &Dataview,
+) -> Vec<(String, Option<Severity>, Option<Severity>)> {
+    let removed = old
+        .row_order
+        .iter()
+        .filter(|row| !new.row_order.contains(row));
+    new.row_order
+        .iter()
+        .chain(removed)
+        .map(|row| {
+            (
+                row.clone(),
+                old.row_max_severity(row),
+                new.row_max_severity(row),
+            )
+        })
+        .filter(|(_, before, after)| before != after)
+        .collect()
+}
+
+/// Returns a minimal view for samples with nothing to report, so the
+/// sampler still shows it is alive.
+///
+/// The view has the given row header, `headline` as its only headline, and
+/// a single `heartbeat` row whose `status` column reads `no data`. It is
+/// built with [`Dataview::builder`], so names are sanitized and validated the
+/// same way, and a row header of `status` fails with
+/// `DataviewError::RowHeaderCollision`.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::dataview::heartbeat;
+/// let view = heartbeat("process", ("lastSample", "2024-01-01T00:00:00Z")).unwrap();
+/// assert_eq!(
+///     view.to_string(),
+///     "process,status\n<!>lastSample,2024-01-01T00:00:00Z\nheartbeat,no data"
+/// );
+/// ```
+pub fn heartbeat(row_header: &str, headline: (&str, &str)) -> Result<Dataview, DataviewError> {
+    let (key, value) = headline;
+    let view = Dataview::builder()
+        .set_row_header(row_header)
+        .add_headline(key, value)
+        .add_value("heartbeat", "status", "no data")
+        .build()?;
+    if view.row_header == "status" {
+        return Err(DataviewError::RowHeaderCollision(view.row_header));
+    }
+    Ok(view)
+}
+
+/// Builds a `Dataview` from a map of row name to a map of column to value.
+///
+/// Rows and columns are ordered by the `BTreeMap` key order; a column's
+/// position is fixed by the first row that contains it.
+///
+/// # Example
+/// ```
+/// use std::collections::BTreeMap;
+/// use geneos_toolkit::dataview::from_map;
+///
+/// let mut rows = BTreeMap::new();
+/// rows.insert(
+///     "proc2".to_string(),
+///     BTreeMap::from([("Status".to_string(), "Stopped".to_string())]),
+/// );
+/// rows.insert(
+///     "proc1".to_string(),
+///     BTreeMap::from([("Status".to_string(), "Running".to_string())]),
+/// );
+///
+/// let view = from_map("Process", &rows).unwrap();
+/// assert_eq!(view.to_string(), "Process,Status\nproc1,Running\nproc2,Stopped");
+/// ```
+pub fn from_map(
+    row_header: &str,
+    map: &BTreeMap<String, BTreeMap<String, String>>,
+) -> Result<Dataview, DataviewError> {
+    let mut builder = DataviewBuilder::new().set_row_header(row_header);
+    for (row, cells) in map {
+        for (column, value) in cells {
+            builder = builder.add_value(row, column, value);
+        }
+    }
+    builder.build()
+}
+
+/// Builds a `Dataview` from a `HashMap` of row name to a map of column to value.
+///
+/// `HashMap` iteration order is unspecified, so rows, and the columns within
+/// each row, are first sorted with `cmp`. A column's position is fixed by the
+/// first row that contains it.
+pub fn from_hash_map<F>(
+    row_header: &str,
+    map: &HashMap<String, HashMap<String, String>>,
+    mut cmp: F,
+) -> Result<Dataview, DataviewError>
+where
+    F: FnMut(&str, &str) -> std::cmp::Ordering,
+{
+    let mut rows: Vec<_> = map.iter().collect();
+    rows.sort_by(|a, b| cmp(a.0, b.0));
+
+    let mut builder = DataviewBuilder::new().set_row_header(row_header);
+    for (row, cells) in rows {
+        let mut cells: Vec<_> = cells.iter().collect();
+        cells.sort_by(|a, b| cmp(a.0, b.0));
+        for (column, value) in cells {
+            builder = builder.add_value(row, column, value);
+        }
+    }
+    builder.build()
+}
+
+/// How fields are protected in delimited input read by [`from_delimited`].
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum Quoting {
+    /// A backslash escapes the next character, as in toolkit output; see
+    /// [`split_escaped`].
+    #[default]
+    Backslash,
+    /// No escaping: every delimiter separates fields.
+    None,
+    /// CSV-style double quotes (RFC 4180): a field wrapped in `"` may hold
+    /// the delimiter, and `""` inside it is a literal quote. A quoted field
+    /// cannot span lines.
+    DoubleQuote,
+}
+
+/// Splits `line` on `delimiter`, honouring RFC 4180 double quotes for
+/// [`Quoting::DoubleQuote`]. An unterminated quote runs to the end of the
+/// line.
+fn split_quoted(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes => {
+                if chars.next_if_eq(&'"').is_some() {
+                    field.push('"');
+                } else {
+                    in_quotes = false;
+                }
+            }
+            '"' if field.is_empty() => in_quotes = true,
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Builds a `Dataview` from delimited text such as CSV, TSV or pipe-separated
+/// data.
+///
+/// The first non-empty line holds the row header followed by the column
+/// names. A later line starting with `<!>` is a headline, `<!>name` and its
+/// value separated by `delimiter`; every other non-empty line holds a row
+/// name followed by its cells. A line with a different number of fields than
+/// the first (or a headline without exactly two) fails with
+/// `DataviewError::FieldCountMismatch` (1-based line number).
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::dataview::{Quoting, from_delimited};
+/// let view = from_delimited("host\tcpu\nweb-01\t12\n", '\t', Quoting::None).unwrap();
+/// assert_eq!(view.to_string(), "host,cpu\nweb-01,12");
+///
+/// let csv = "host,status\n<!>region,eu\nweb-01,\"up, mostly\"\n";
+/// let view = from_delimited(csv, ',', Quoting::DoubleQuote).unwrap();
+/// assert_eq!(view.headline("region"), Some(&"eu".to_string()));
+/// assert_eq!(view.value("web-01", "status"), Some(&"up, mostly".to_string()));
+/// ```
+pub fn from_delimited(
+    input: &str,
+    delimiter: char,
+    quoting: Quoting,
+) -> Result<Dataview, DataviewError> {
+    let split = |line: &str| -> Vec<String> {
+        match quoting {
+            Quoting::Backslash => split_escaped(line, delimiter),
+            Quoting::None => line.split(delimiter).map(str::to_string).collect(),
+            Quoting::DoubleQuote => split_quoted(line, delimiter),
+        }
+    };
+
+    let mut lines = input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.is_empty());
+    let (_, header) = lines.next().ok_or(DataviewError::MissingRowHeader)?;
+    let mut header = split(header).into_iter();
+    let row_header = header.next().unwrap_or_default();
+    let columns: Vec<String> = header.collect();
+    let column_refs: Vec<&str> = columns.iter().map(String::as_str).collect();
+
+    let mut builder = DataviewBuilder::new()
+        .set_row_header(&row_header)
+        .set_columns(&column_refs);
+    for (idx, line) in lines {
+        if let Some(headline) = line.strip_prefix("<!>") {
+            let fields = split(headline);
+            let [key, value] = <[String; 2]>::try_from(fields).map_err(|fields| {
+                DataviewError::FieldCountMismatch {
+                    line: idx + 1,
+                    expected: 2,
+                    found: fields.len(),
+                }
+            })?;
+            builder = builder.add_headline(&key, value);
+            continue;
+        }
+        let fields = split(line);
+        if fields.len() != columns.len() + 1 {
+            return Err(DataviewError::FieldCountMismatch {
+                line: idx + 1,
+                expected: columns.len() + 1,
+                found: fields.len(),
+            });
+        }
+        let mut fields = fields.into_iter();
+        let row = fields.next().unwrap_or_default();
+        for (column, value) in columns.iter().zip(fields) {
+            builder = builder.add_value_owned(&row, column, value);
+        }
+    }
+    builder.build()
+}
+
+/// An error encountered while parsing toolkit output.
+#[derive(Debug)]
+pub enum ParseError {
+    Io(io::Error),
+    MissingHeader,
+    /// A data row has more fields than the header, usually because a
+    /// delimiter was not escaped upstream. `line` is 1-based and both counts
+    /// include the row name field.
+    ColumnCountMismatch {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "IO error: {e}"),
+            ParseError::MissingHeader => write!(f, "Toolkit output has no header row"),
+            ParseError::ColumnCountMismatch {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Line {line} has {found} fields but the header has {expected}"
+            ),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ParseError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ParseError {
+    fn from(err: io::Error) -> Self {
+        ParseError::Io(err)
+    }
+}
+
+/// Error returned by [`Dataview::assert_roundtrips`].
+#[derive(Debug)]
+pub enum RoundtripError {
+    /// The rendered output could not be parsed.
+    Parse(ParseError),
+    /// A parsed item differs from the original view.
+    Mismatch {
+        location: String,
+        expected: String,
+        found: String,
+    },
+}
+
+impl fmt::Display for RoundtripError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RoundtripError::Parse(e) => write!(f, "Rendered output does not parse: {e}"),
+            RoundtripError::Mismatch {
+                location,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Round-trip mismatch at {location}: expected '{expected}', found '{found}'"
+            ),
+        }
+    }
+}
+
+impl Error for RoundtripError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            RoundtripError::Parse(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<ParseError> for RoundtripError {
+    fn from(err: ParseError) -> Self {
+        RoundtripError::Parse(err)
+    }
+}
+
+/// Error returned by [`Dataview::join_columns`].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum MergeError {
+    /// The views have different row headers.
+    RowHeaderMismatch { left: String, right: String },
+    /// Both views have a column with this name.
+    ColumnConflict(String),
+}
+
+impl fmt::Display for MergeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeError::RowHeaderMismatch { left, right } => {
+                write!(f, "Row headers differ: '{left}' and '{right}'")
+            }
+            MergeError::ColumnConflict(column) => {
+                write!(f, "Column '{column}' exists in both views")
+            }
+        }
+    }
+}
+
+impl Error for MergeError {}
+
+/// Lazily parses toolkit output line by line, without loading it all into memory.
+///
+/// The first item is the header: the row header paired with each column name
+/// and an empty value. Each following item is a data row: the row name and
+/// its `(column, value)` cells, in column order. Headline lines are skipped
+/// and all fields are un-escaped. An empty input yields
+/// `ParseError::MissingHeader`, and a data row with more fields than the
+/// header yields `ParseError::ColumnCountMismatch`. Rows with fewer fields are
+/// valid; their trailing cells are simply missing.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::dataview::parse_rows;
+///
+/// let input = "host,status\n<!>region,eu\nweb-01,up\\, ok\n";
+/// let mut rows = parse_rows(input.as_bytes());
+///
+/// let (row_header, _) = rows.next().unwrap().unwrap();
+/// assert_eq!(row_header, "host");
+///
+/// let (row, cells) = rows.next().unwrap().unwrap();
+/// assert_eq!(row, "web-01");
+/// assert_eq!(cells, vec![("status".to_string(), "up, ok".to_string())]);
+/// assert!(rows.next().is_none());
+/// ```
+pub fn parse_rows<R: io::BufRead>(
+    reader: R,
+) -> impl Iterator<Item = Result<(String, Vec<(String, String)>), ParseError>> {
+    RowParser {
+        lines: reader.lines(),
+        line_num: 0,
+        columns: None,
+        done: false,
+    }
+}
+
+struct RowParser<R> {
+    lines: io::Lines<R>,
+    line_num: usize,
+    columns: Option<Vec<String>>,
+    done: bool,
+}
+
+impl<R: io::BufRead> Iterator for RowParser<R> {
+    type Item = Result<(String, Vec<(String, String)>), ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        loop {
+            let line = match self.lines.next() {
+                Some(Ok(line)) => line,
+                Some(Err(e)) => {
+                    self.done = true;
+                    return Some(Err(e.into()));
+                }
+                None => {
+                    self.done = true;
+                    return match self.columns {
+                        Some(_) => None,
+                        None => Some(Err(ParseError::MissingHeader)),
+                    };
+                }
+            };
+            self.line_num += 1;
+
+            let Some(columns) = &self.columns else {
+                let mut fields = split_escaped(&line, ',').into_iter();
+                let row_header = fields.next().unwrap_or_default();
+                let columns: Vec<String> = fields.collect();
+                let cells = columns.iter().map(|c| (c.clone(), String::new())).collect();
+                self.columns = Some(columns);
+                return Some(Ok((row_header, cells)));
+            };
+
+            if line.starts_with("<!>") || line.is_empty() {
+                continue;
+            }
+
+            let fields = split_escaped(&line, ',');
+            if fields.len() > columns.len() + 1 {
+                return Some(Err(ParseError::ColumnCountMismatch {
+                    line: self.line_num,
+                    expected: columns.len() + 1,
+                    found: fields.len(),
+                }));
+            }
+
+            let mut fields = fields.into_iter();
+            let row = fields.next().unwrap_or_default();
+            let cells = columns.iter().cloned().zip(fields).collect();
+            return Some(Ok((row, cells)));
+        }
+    }
+}
+
+struct BuilderPreview<'a>(&'a DataviewBuilder);
+
+impl fmt::Display for BuilderPreview<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let builder = self.0;
+        writeln!(f, "# PREVIEW (not valid toolkit output)")?;
+        write_header_row(
+            f,
+            builder.row_header.as_deref().unwrap_or("<unset>"),
+            &builder.column_order,
+        )?;
+        write_headlines(
+            f,
+            &builder.headline_order,
+            builder.headlines.as_ref().unwrap_or(&HashMap::new()),
+        )?;
+        write_data_rows(
+            f,
+            &builder.row_order,
+            &builder.column_order,
+            builder.values.as_ref().unwrap_or(&HashMap::new()),
+            &builder.column_defaults,
+        )
+    }
+}
+
+/// Prints the result of a Dataview operation and exits the program.
+///
+/// # Arguments
+/// - `dataview`: The `Result` of a Dataview operation, holding either a `Dataview` or a `DataviewError`.
+///
+/// # Returns
+/// - Exits the program with a status code of 0 if successful, or 1 if an error occurred.
+///
+/// # Example
+/// ```rust
+/// use geneos_toolkit::prelude::*;
+///
+/// let dataview = Dataview::builder()
+///    .set_row_header("ID")
+///    .add_headline("Total", "42")
+///    .add_value("1", "Name", "Alice")
+///    .build();
+///
+/// print_result_and_exit(dataview)
+/// ```
+/// Prints the dataview on success or an error on failure, then exits the process.
+pub fn print_result_and_exit(dataview: Result<Dataview, DataviewError>) -> ! {
+    match dataview {
+        Ok(v) => {
+            println!("{v}");
+            std::process::exit(0)
+        }
+        Err(e) => {
+            eprintln!("ERROR: {e}");
+            std::process::exit(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    /// Helper function to create a basic dataview for testing
+    fn create_basic_dataview() -> Result<Dataview, DataviewError> {
+        DataviewBuilder::new()
+            .set_row_header("ID")
+            .add_headline("AverageAge", "30")
+            .add_value("1", "Name", "Alice")
+            .add_value("1", "Age", "30")
+            .build()
+    }
+
+    #[test]
+    fn test_dataview_builder_single_row() -> Result<(), DataviewError> {
+        let dataview = create_basic_dataview()?;
+
+        // Test row header
+        assert_eq!(dataview.row_header(), "ID");
+
+        // Test headline
+        assert_eq!(dataview.headline("AverageAge"), Some(&"30".to_string()));
+
+        // Test values
+        assert_eq!(dataview.value("1", "Name"), Some(&"Alice".to_string()));
+        assert_eq!(dataview.value("1", "Age"), Some(&"30".to_string()));
+
+        // Test structure
+        assert_eq!(dataview.row_order().len(), 1);
+        assert_eq!(dataview.column_order().len(), 2);
+        assert!(dataview.column_order().contains(&"Name".to_string()));
+        assert!(dataview.column_order().contains(&"Age".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dataview_display_format() -> Result<(), DataviewError> {
+        // Test basic display
+        let dataview = create_basic_dataview()?;
+        assert_eq!(
+            dataview.to_string(),
+            "\
+ID,Name,Age
+<!>AverageAge,30
+1,Alice,30"
+        );
+
+        // Test multiple rows and columns
+        let multi_row_dataview = DataviewBuilder::new()
+            .set_row_header("id")
+            // Ensure that headlines appear in the order in which they were added.
+            .add_headline("Baz", "Foo")
+            .add_headline("AlertDetails", "this is red alert")
+            .add_value("001", "name", "agila")
+            .add_value("001", "status", "up")
+            .add_value("001", "Value", "97")
+            .add_value("002", "name", "lawin")
+            .add_value("002", "status", "down")
+            .add_value("002", "Value", "85")
+            .build()?;
+
+        let expected_output = "\
+id,name,status,Value
+<!>Baz,Foo
+<!>AlertDetails,this is red alert
+001,agila,up,97
+002,lawin,down,85";
+
+        assert_eq!(multi_row_dataview.to_string(), expected_output);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_special_characters_escaping() -> Result<(), DataviewError> {
+        // Test comma escaping in row header, columns, values
+        let dataview = DataviewBuilder::new()
+            .set_row_header("queue,id")
+            .add_value("queue3", "number,code", "7,331")
+            .add_value("queue3", "count", "45,000")
+            .add_value("queue3", "ratio", "0.16")
+            .add_value("queue3", "status", "online")
+            .build()?;
+
+        let expected_output = "\
+queue\\,id,number\\,code,count,ratio,status
+queue3,7\\,331,45\\,000,0.16,online";
+
+        assert_eq!(dataview.to_string(), expected_output);
+
+        // Test other special characters
+        let dataview_special = DataviewBuilder::new()
+            .set_row_header("special")
+            .add_headline("special,headline", "headline value with, comma")
+            .add_value("special_case", "state", "testing: \"quotes\" & <symbols>")
+            .add_value("special_case", "data", "multi-line\ntext")
+            .build()?;
+
+        let output = dataview_special.to_string();
+        assert!(output.contains("special"));
+        assert!(output.contains("<!>special\\,headline,headline value with\\, comma"));
+        assert!(output.contains("testing: \"quotes\" & <symbols>"));
+        assert!(output.contains("multi-line\\ntext"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_and_missing_values() -> Result<(), DataviewError> {
+        // Test with some missing values
+        let dataview = DataviewBuilder::new()
+            .set_row_header("item")
+            .add_value("item1", "col1", "value1")
+            .add_value("item1", "col2", "value2")
+            .add_value("item2", "col1", "value3")
+            // Deliberately missing item2/col2
+            .add_value("item3", "col3", "value4") // New column not in other rows
+            .build()?;
+
+        let output = dataview.to_string();
+
+        // Verify output format has empty cells where expected
+        assert!(output.contains("item1,value1,value2,"));
+        assert!(output.contains("item2,value3,,"));
+        assert!(output.contains("item3,,,value4"));
+
+        // Test accessing missing values
+        assert_eq!(dataview.value("item2", "col2"), None);
+        assert_eq!(dataview.value("nonexistent", "col1"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dataview_complex() -> Result<(), DataviewError> {
+        // This test creates a more realistic Dataview with many rows, columns and headlines
+        let dataview = DataviewBuilder::new()
+            .set_row_header("cpu")
+            // Add multiple headlines
+            .add_headline("numOnlineCpus", "4")
+            .add_headline("loadAverage1Min", "0.32")
+            .add_headline("loadAverage5Min", "0.45")
+            .add_headline("loadAverage15Min", "0.38")
+            .add_headline("HyperThreadingStatus", "ENABLED")
+            // CPU average row
+            .add_value("Average_cpu", "percentUtilisation", "3.75 %")
+            .add_value("Average_cpu", "percentUserTime", "2.15 %")
+            .add_value("Average_cpu", "percentKernelTime", "1.25 %")
+            .add_value("Average_cpu", "percentIdle", "96.25 %")
+            // CPU 0 with values in all columns
+            .add_value("cpu_0", "type", "GenuineIntel Intel(R)")
+            .add_value("cpu_0", "state", "on-line")
+            .add_value("cpu_0", "clockSpeed", "2500.00 MHz")
+            .add_value("cpu_0", "percentUtilisation", "3.25 %")
+            .add_value("cpu_0", "percentUserTime", "1.95 %")
+            .add_value("cpu_0", "percentKernelTime", "1.30 %")
+            .add_value("cpu_0", "percentIdle", "96.75 %")
+            // CPU 1 with same structure
+            .add_value("cpu_1", "type", "GenuineIntel Intel(R)")
+            .add_value("cpu_1", "state", "on-line")
+            .add_value("cpu_1", "clockSpeed", "2500.00 MHz")
+            .add_value("cpu_1", "percentUtilisation", "4.25 %")
+            .add_value("cpu_1", "percentUserTime", "2.35 %")
+            .add_value("cpu_1", "percentKernelTime", "1.20 %")
+            .add_value("cpu_1", "percentIdle", "95.75 %")
+            // cpu_2 with a comma in one value (needs escaping) and some missing values
+            .add_value("cpu_2", "type", "GenuineIntel, Intel(R)")
+            .add_value("cpu_2", "state", "on-line")
+            .add_value("cpu_2", "clockSpeed", "2,500.00 MHz")
+            // Add another logical CPU
+            .add_value("cpu_0_logical#1", "type", "logical")
+            .add_value("cpu_0_logical#1", "state", "on-line")
+            .add_value("cpu_0_logical#1", "clockSpeed", "2500.00 MHz")
+            .add_value("cpu_0_logical#1", "percentUtilisation", "2.54 %")
+            .build()?;
+
+        // Get the output
+        let output = dataview.to_string();
+
+        // Check structure
+        assert_eq!(dataview.row_order().len(), 5); // 5 rows
+        assert_eq!(dataview.row_order()[0], "Average_cpu".to_string());
+        assert_eq!(dataview.row_order()[1], "cpu_0".to_string());
+        assert_eq!(dataview.row_order()[2], "cpu_1".to_string());
+        assert_eq!(dataview.row_order()[3], "cpu_2".to_string());
+        assert_eq!(dataview.row_order()[4], "cpu_0_logical#1".to_string());
+
+        assert_eq!(dataview.headlines.len(), 5); // 5 headlines
+
+        // Assert column ordering is preserved
+        let expected_columns = [
+            "percentUtilisation",
+            "percentUserTime",
+            "percentKernelTime",
+            "percentIdle",
+            "type",
+            "state",
+            "clockSpeed",
+        ];
+        for (idx, col) in expected_columns.iter().enumerate() {
+            if idx < dataview.column_order().len() {
+                assert!(dataview.column_order().contains(&col.to_string()));
+            }
+        }
+
+        // Basic format checks
+        assert!(output.starts_with("cpu,"));
+        assert!(output.contains("<!>numOnlineCpus,4\n"));
+        assert!(output.contains("<!>loadAverage1Min,0.32\n"));
+        assert!(output.contains("<!>HyperThreadingStatus,ENABLED\n"));
+
+        // Check comma escaping
+        assert!(output.contains("GenuineIntel\\, Intel(R)"));
+        assert!(output.contains("2\\,500.00 MHz"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_error_conditions() -> Result<(), ()> {
+        // Test missing row header
+        let result = DataviewBuilder::new()
+            .add_value("row1", "col1", "value1")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::MissingRowHeader)));
+
+        // Test missing values
+        let result = DataviewBuilder::new().set_row_header("header").build();
+
+        assert!(matches!(result, Err(DataviewError::MissingValue)));
+
+        // Ensure headlines alone are not enough
+        let result = DataviewBuilder::new()
+            .set_row_header("header")
+            .add_headline("headline1", "value1")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::MissingValue)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_builder() -> Result<(), DataviewError> {
+        let row1 = Row::new("process1")
+            .add_cell("Status", "Running")
+            .add_cell("CPU", "2.5%");
+
+        let row2 = Row::new("process2")
+            .add_cell("Status", "Stopped")
+            .add_cell("CPU", "0.0%");
+
+        let dataview = Dataview::builder()
+            .set_row_header("Process")
+            .add_row(row1)
+            .add_row(row2)
+            .build()?;
+
+        let output = dataview.to_string();
+
+        assert!(output.contains("Process,Status,CPU"));
+        assert!(output.contains("process1,Running,2.5%"));
+        assert!(output.contains("process2,Stopped,0.0%"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_accessors() {
+        let row = Row::new("process1");
+        assert_eq!(row.name(), "process1");
+        assert_eq!(row.len(), 0);
+        assert!(row.is_empty());
+
+        let row = row.add_cell("Status", "Running").add_cell("CPU", "2.5%");
+        assert_eq!(row.name(), "process1");
+        assert_eq!(row.len(), 2);
+        assert!(!row.is_empty());
+    }
+
+    #[test]
+    fn test_fill_cells_grid() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("x")
+            .fill_cells(&["2", "3"], &["4", "5", "skip"], |r, c| {
+                let (r, c): (u32, u32) = (r.parse().ok()?, c.parse().ok()?);
+                Some((r * c).to_string())
+            })
+            .build()?;
+
+        assert_eq!(dataview.to_string(), "x,4,5\n2,8,10\n3,12,15");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_grouped_values() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_grouped_values("web-01", "cpu", &[("user", "12%"), ("system", "3%")])
+            .set_group_separator("_")
+            .add_grouped_values("web-01", "mem", &[("used", "2GB")])
+            .build()?;
+
+        assert_eq!(
+            dataview.column_order(),
+            &["cpu.user", "cpu.system", "mem_used"]
+        );
+        assert_eq!(
+            dataview.value("web-01", "cpu.system"),
+            Some(&"3%".to_string())
+        );
+        assert_eq!(
+            dataview.value("web-01", "mem_used"),
+            Some(&"2GB".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_columns_fixes_header_order() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .set_columns(&["status", "cpu", "mem"])
+            .add_value("web-01", "mem", "1GB")
+            .add_value("web-01", "disk", "40%")
+            .add_value("web-02", "cpu", "20%")
+            .add_value("web-02", "status", "up")
+            .build()?;
+
+        assert_eq!(dataview.column_order(), &["status", "cpu", "mem", "disk"]);
+        assert_eq!(
+            dataview.to_string(),
+            "host,status,cpu,mem,disk\nweb-01,,,1GB,40%\nweb-02,up,20%,,"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_aggregate_headlines() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("queue")
+            .add_headline("source", "mq")
+            .add_aggregate_headline("totalDepth", "depth", Aggregate::Sum)
+            .add_aggregate_headline("avgDepth", "depth", Aggregate::Avg)
+            .add_aggregate_headline("minDepth", "depth", Aggregate::Min)
+            .add_aggregate_headline("maxDepth", "depth", Aggregate::Max)
+            .add_aggregate_headline("numericQueues", "depth", Aggregate::Count)
+            .add_aggregate_headline("noData", "missing", Aggregate::Avg)
+            .add_value("q1", "depth", "10")
+            .add_value("q2", "depth", " 5 ")
+            .add_value("q3", "depth", "n/a")
+            .add_value("q4", "depth", "15")
+            .add_value("q5", "depth", "NaN")
+            .add_value("q6", "depth", "inf")
+            .add_value("q7", "depth", "-infinity")
+            .build()?;
+
+        assert_eq!(dataview.headline("totalDepth"), Some(&"30".to_string()));
+        assert_eq!(dataview.headline("avgDepth"), Some(&"10".to_string()));
+        assert_eq!(dataview.headline("minDepth"), Some(&"5".to_string()));
+        assert_eq!(dataview.headline("maxDepth"), Some(&"15".to_string()));
+        assert_eq!(dataview.headline("numericQueues"), Some(&"3".to_string()));
+        assert_eq!(dataview.headline("noData"), Some(&String::new()));
+        assert_eq!(
+            dataview.headline_order(),
+            &[
+                "source",
+                "totalDepth",
+                "avgDepth",
+                "minDepth",
+                "maxDepth",
+                "numericQueues",
+                "noData"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_headline_overwrites_value_preserves_order() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("id")
+            .add_headline("Status", "initial")
+            .add_headline("Count", "10")
+            .add_headline("Status", "updated")
+            .add_value("r1", "col", "val")
+            .build()?;
+
+        // Value should be overwritten
+        assert_eq!(dataview.headline("Status"), Some(&"updated".to_string()));
+        assert_eq!(dataview.headline("Count"), Some(&"10".to_string()));
+
+        // Order should reflect first insertion: Status before Count
+        assert_eq!(dataview.headline_order(), &["Status", "Count"]);
+
+        // Display should use updated value in original position
+        let output = dataview.to_string();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines[1], "<!>Status,updated");
+        assert_eq!(lines[2], "<!>Count,10");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_cell_overwrites_value_preserves_order() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("id")
+            .add_value("row1", "colA", "first")
+            .add_value("row1", "colB", "other")
+            .add_value("row2", "colA", "x")
+            .add_value("row1", "colA", "second")
+            .build()?;
+
+        // Value should be overwritten
+        assert_eq!(dataview.value("row1", "colA"), Some(&"second".to_string()));
+
+        // Row and column order should reflect first insertion only (no duplicates)
+        assert_eq!(dataview.row_order(), &["row1", "row2"]);
+        assert_eq!(dataview.column_order(), &["colA", "colB"]);
+
+        // Display should use the overwritten value
+        let output = dataview.to_string();
+        assert!(output.contains("row1,second,other"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_backslash_escaping() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("path\\id")
+            .add_headline("dir", "C:\\Users\\test")
+            .add_value("row\\1", "col\\a", "val\\ue")
+            .build()?;
+
+        let output = dataview.to_string();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert_eq!(lines[0], "path\\\\id,col\\\\a");
+        assert_eq!(lines[1], "<!>dir,C:\\\\Users\\\\test");
+        assert_eq!(lines[2], "row\\\\1,val\\\\ue");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accessor_methods_nonexistent_keys() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("id")
+            .add_headline("exists", "yes")
+            .add_value("row1", "col1", "val1")
+            .build()?;
+
+        assert_eq!(dataview.headline("nonexistent"), None);
+        assert_eq!(dataview.value("row1", "nonexistent"), None);
+        assert_eq!(dataview.value("nonexistent", "col1"), None);
+        assert_eq!(dataview.value("nonexistent", "nonexistent"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_headlines_preserves_order() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("source", "agent")
+            .add_headlines(vec![
+                ("zone".to_string(), "b".to_string()),
+                ("region".to_string(), "eu".to_string()),
+                ("source".to_string(), "poller".to_string()),
+            ])
+            .add_headlines(BTreeMap::from([("uptime", 42), ("cores", 8)]))
+            .add_value("web-01", "cpu", "10")
+            .build()?;
+
+        assert_eq!(
+            dataview.headline_order(),
+            &["source", "zone", "region", "cores", "uptime"]
+        );
+        assert_eq!(dataview.headline("source"), Some(&"poller".to_string()));
+        assert_eq!(dataview.headline("cores"), Some(&"8".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dataview_no_headlines() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("item")
+            .add_value("a", "x", "1")
+            .add_value("b", "x", "2")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(!output.contains("<!>"));
+        assert_eq!(output, "item,x\na,1\nb,2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_golden_snapshot_representative_dataview() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("service")
+            .add_headline("environment", "production")
+            .add_headline("region", "eu-west-1")
+            .add_value("api-gateway", "status", "running")
+            .add_value("api-gateway", "latency_ms", "12")
+            .add_value("api-gateway", "errors", "0")
+            .add_value("db-primary", "status", "running")
+            .add_value("db-primary", "latency_ms", "3")
+            .add_value("db-primary", "errors", "0")
+            .add_value("cache", "status", "degraded")
+            .add_value("cache", "latency_ms", "45")
+            .add_value("cache", "errors", "7")
+            .build()?;
+
+        let expected = "\
+service,status,latency_ms,errors
+<!>environment,production
+<!>region,eu-west-1
+api-gateway,running,12,0
+db-primary,running,3,0
+cache,degraded,45,7";
+
+        assert_eq!(dataview.to_string(), expected);
+
+        Ok(())
+    }
+
+    // === C1: <!> headline injection ===
+
+    #[test]
+    fn test_escape_headline_prefix_in_row_name() -> Result<(), DataviewError> {
+        // A row name starting with <!> must not render as a headline
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("<!>AlertSeverity,OK", "status", "injected")
+            .build()?;
+
+        let output = dataview.to_string();
+        // The data row must NOT start with raw <!>
+        let data_lines: Vec<&str> = output.lines().filter(|l| !l.starts_with("<!>")).collect();
+        assert!(data_lines.len() >= 2, "Should have header + data row");
+        let data_row = data_lines[1];
+        assert!(
+            !data_row.starts_with("<!>"),
+            "Row name must not produce a fake headline: {data_row}"
+        );
+        // The escaped form should appear
+        assert!(data_row.contains("\\<!>"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_headline_prefix_in_value() -> Result<(), DataviewError> {
+        // A value starting with <!> should be escaped
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "col", "<!>Fake,headline")
+            .build()?;
+
+        let output = dataview.to_string();
+        // Value should contain escaped form
+        assert!(output.contains("\\<!>Fake"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_escape_headline_prefix_in_row_header() -> Result<(), DataviewError> {
+        // Row header starting with <!> must be escaped
+        let dataview = Dataview::builder()
+            .set_row_header("<!>header")
+            .add_value("row1", "col", "val")
+            .build()?;
+
+        let output = dataview.to_string();
+        let first_line = output.lines().next().unwrap();
+        assert!(
+            first_line.starts_with("\\<!>header"),
+            "Row header must escape <!>: {first_line}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_headline_key_with_headline_prefix_rejected() {
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .add_headline("<!>weird", "1")
+            .add_value("r", "c", "v")
+            .build();
+
+        match result {
+            Err(DataviewError::HeadlinePrefixInKey(key)) => assert_eq!(key, "<!>weird"),
+            other => panic!("expected HeadlinePrefixInKey, got {other:?}"),
+        }
+
+        // Only a leading marker is rejected
+        assert!(
+            Dataview::builder()
+                .set_row_header("id")
+                .add_headline("not<!>weird", "1")
+                .add_value("r", "c", "v")
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_headline_prefix_mid_string_not_escaped() {
+        // <!> only matters at string start — mid-string is harmless
+        let escaped = "some<!>text".escape_nasty_chars();
+        assert_eq!(escaped, "some<!>text");
+    }
+
+    #[test]
+    fn test_headline_escaped_like_data_cell() -> Result<(), DataviewError> {
+        // Headlines and data rows share one escaping style
+        let value = "a,b\\c\nd";
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_headline("note", value)
+            .add_value("r1", "note", value)
+            .build()?;
+
+        let output = dataview.to_string();
+        let mut lines = output.lines().skip(1);
+        let headline = lines.next().unwrap().strip_prefix("<!>note,").unwrap();
+        let cell = lines.next().unwrap().strip_prefix("r1,").unwrap();
+        assert_eq!(headline, cell);
+        assert_eq!(headline, "a\\,b\\\\c\\nd");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tab_in_cell_round_trips_with_comma_delimiter() -> Result<(), DataviewError> {
+        // Output is always comma-delimited, so a tab is an ordinary character
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("r1", "note", "a\tb,c")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert_eq!(output, "id,note\nr1,a\tb\\,c");
+        assert!(dataview.validate().is_ok());
+        let line = output.lines().nth(1).unwrap();
+        assert_eq!(split_escaped(line, ','), vec!["r1", "a\tb,c"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_real_headlines_unaffected() -> Result<(), DataviewError> {
+        // Legitimate headlines must still render with <!> prefix
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_headline("Status", "OK")
+            .add_value("r1", "c1", "v1")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(output.contains("<!>Status,OK"));
+
+        Ok(())
+    }
+
+    // === H1: null byte passthrough ===
+
+    #[test]
+    fn test_escape_null_byte() {
+        let escaped = "before\0after".escape_nasty_chars();
+        assert_eq!(escaped, "before\\0after");
+        assert!(!escaped.contains('\0'));
+    }
+
+    #[test]
+    fn test_null_byte_in_value() -> Result<(), DataviewError> {
+        // Null bytes are stripped by unicode sanitizer (defense in depth:
+        // escape_nasty_chars would also escape them if they got through)
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "col", "legitimate\0<!>INJECTED")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(
+            !output.contains('\0'),
+            "Null bytes must not appear in output"
+        );
+        // \0 is stripped, so "legitimate" and "<!>INJECTED" are concatenated
+        assert!(output.contains("legitimate<!>INJECTED"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_null_byte_in_row_name() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row\x001", "col", "val")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(!output.contains('\0'));
+
+        Ok(())
+    }
+
+    // === M3: unicode control character stripping ===
+
+    #[test]
+    fn test_strip_rtl_override() -> Result<(), DataviewError> {
+        // U+202E (RTL override) can make "KO" display as "OK" — must be stripped
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "status", "\u{202E}KO")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(
+            !output.contains('\u{202E}'),
+            "RTL override must be stripped"
+        );
+        assert!(output.contains("KO"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_zero_width_space() -> Result<(), DataviewError> {
+        // U+200B (zero-width space) breaks rule matching
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "col", "OK\u{200B}status")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(!output.contains('\u{200B}'));
+        assert!(output.contains("OKstatus"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_bom() -> Result<(), DataviewError> {
+        // U+FEFF (BOM) at start can confuse encoding detection
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "col", "\u{FEFF}value")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(!output.contains('\u{FEFF}'));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_preserve_ascii_whitespace() -> Result<(), DataviewError> {
+        // Tab and space must survive stripping (they are useful ASCII control/whitespace)
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "col", "hello\tworld here")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(output.contains("hello\tworld here"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_unicode_controls_opt_out() -> Result<(), DataviewError> {
+        // When strip_unicode_controls(false), control chars pass through
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .strip_unicode_controls(false)
+            .add_value("row1", "col", "\u{202E}KO")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(
+            output.contains('\u{202E}'),
+            "RTL override should be preserved when stripping is disabled"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_unicode_in_headline_key_and_value() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_headline("stat\u{200B}us", "O\u{202E}K")
+            .add_value("r1", "c1", "v1")
+            .build()?;
+
+        let output = dataview.to_string();
+        assert!(output.contains("<!>status,OK"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_strip_unicode_in_row_and_column_names() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("i\u{FEFF}d")
+            .add_value("ro\u{200B}w1", "co\u{202E}l", "val")
+            .build()?;
+
+        let output = dataview.to_string();
+        let first_line = output.lines().next().unwrap();
+        assert_eq!(first_line, "id,col");
+
+        Ok(())
+    }
+
+    // === L5: empty row/column name rejection ===
+
+    #[test]
+    fn test_reject_empty_row_header() {
+        let result = Dataview::builder()
+            .set_row_header("")
+            .add_value("row1", "col", "val")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::EmptyName { .. })));
+    }
+
+    #[test]
+    fn test_reject_empty_row_name() {
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .add_value("", "col", "val")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::EmptyName { .. })));
+    }
+
+    #[test]
+    fn test_reject_empty_row_name_from_row_builder() {
+        // A row id taken from an empty field is caught at build time
+        let id_field = "";
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .add_row(Row::new(id_field).add_cell("col", "val"))
+            .build();
+
+        let err = result.expect_err("expected error for empty row name");
+        assert!(matches!(err, DataviewError::EmptyName { ref kind, .. } if kind == "row"));
+        assert_eq!(
+            err.to_string(),
+            "Empty row name is not allowed (column 'col')"
+        );
+    }
+
+    #[test]
+    fn test_error_display_includes_identifiers() -> Result<(), DataviewError> {
+        let mut dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "up")
+            .build()?;
+
+        let err = dataview
+            .apply_update(&[("web-02".to_string(), String::new(), "up".to_string())])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Empty column name is not allowed (row 'web-02')"
+        );
+        let err = dataview
+            .apply_update(&[(String::new(), "status".to_string(), "up".to_string())])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Empty row name is not allowed (column 'status')"
+        );
+        assert!(err.source().is_none());
+
+        let err = dataview.with_row_header("status").unwrap_err();
+        assert!(err.to_string().contains("'status'"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_from_columns() -> Result<(), DataviewError> {
+        let row = Row::from_columns("cpu0", &["user", "idle", "system"], |col| {
+            (col != "idle").then(|| col.len().to_string())
+        });
+        assert_eq!(row.name(), "cpu0");
+        assert_eq!(row.len(), 2);
+
+        let dataview = Dataview::builder()
+            .set_row_header("cpu")
+            .set_columns(&["user", "idle", "system"])
+            .add_row(row)
+            .build()?;
+        assert_eq!(dataview.to_string(), "cpu,user,idle,system\ncpu0,4,,6");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reject_empty_column_name() {
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .add_value("row1", "", "val")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::EmptyName { .. })));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "Empty column name is not allowed (row 'row1')"
+        );
+    }
+
+    #[test]
+    fn test_reject_empty_headline_key() {
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .add_headline("", "val")
+            .add_value("row1", "col", "val")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::EmptyName { .. })));
+    }
+
+    #[test]
+    fn test_whitespace_only_name_after_stripping() {
+        // A name that is only unicode control chars becomes empty after stripping
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .add_value("\u{200B}\u{FEFF}", "col", "val")
+            .build();
+
+        assert!(matches!(result, Err(DataviewError::EmptyName { .. })));
+    }
+
+    #[test]
+    fn test_case_insensitive_columns() {
+        let result = Dataview::builder()
+            .set_row_header("host")
+            .case_insensitive_columns()
+            .add_value("web-01", "CPU", "10%")
+            .add_value("web-01", "mem", "1GB")
+            .add_value("web-02", "cpu", "20%")
+            .build();
+
+        match result {
+            Err(DataviewError::DuplicateColumn(first, second)) => {
+                assert_eq!(first, "CPU");
+                assert_eq!(second, "cpu");
+            }
+            other => panic!("expected DuplicateColumn, got {other:?}"),
+        }
+
+        // Default mode keeps case-distinct columns
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "CPU", "10%")
+            .add_value("web-02", "cpu", "20%")
+            .build()
+            .unwrap();
+        assert_eq!(dataview.column_order(), &["CPU", "cpu"]);
+    }
+
+    #[test]
+    fn test_require_headline() {
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .require_headline()
+            .add_value("r1", "c1", "v1")
+            .build();
+        assert!(matches!(result, Err(DataviewError::MissingHeadline)));
+
+        let result = Dataview::builder()
+            .set_row_header("id")
+            .require_headline()
+            .add_headline("source", "test")
+            .add_value("r1", "c1", "v1")
+            .build();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_row_sorting_methods() -> Result<(), DataviewError> {
+        // Default: insertion order preserved
+        let default = Dataview::builder()
+            .set_row_header("id")
+            .add_value("b", "col", "1")
+            .add_value("a", "col", "1")
+            .add_value("c", "col", "1")
+            .build()?;
+        assert_eq!(default.row_order(), &["b", "a", "c"]);
+
+        // sort_rows: ascending by row name
+        let sorted = Dataview::builder()
+            .set_row_header("id")
+            .add_value("b", "col", "1")
+            .add_value("a", "col", "1")
+            .add_value("c", "col", "1")
+            .sort_rows()
+            .build()?;
+        assert_eq!(sorted.row_order(), &["a", "b", "c"]);
+
+        // sort_rows_by: custom key (length)
+        let by_len = Dataview::builder()
+            .set_row_header("id")
+            .add_row(Row::new("long").add_cell("v", "1"))
+            .add_row(Row::new("mid").add_cell("v", "1"))
+            .add_row(Row::new("s").add_cell("v", "1"))
+            .sort_rows_by(|name| name.len())
+            .build()?;
+        assert_eq!(by_len.row_order(), &["s", "mid", "long"]);
+
+        // sort_rows_with: custom comparator (reverse lexicographic)
+        let reversed = Dataview::builder()
+            .set_row_header("id")
+            .add_row(Row::new("alpha").add_cell("v", "1"))
+            .add_row(Row::new("beta").add_cell("v", "1"))
+            .add_row(Row::new("gamma").add_cell("v", "1"))
+            .sort_rows_with(|a, b| b.cmp(a))
+            .build()?;
+        assert_eq!(reversed.row_order(), &["gamma", "beta", "alpha"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_max_severity() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("check")
+            .add_value("disk", "space", "ok")
+            .add_value("disk", "inodes", "Critical")
+            .add_value("disk", "usage", "97%")
+            .add_value("net", "link", "WARNING")
+            .add_value("cpu", "usage", "12%")
+            .build()?;
+
+        assert_eq!(dataview.row_max_severity("disk"), Some(Severity::Critical));
+        assert_eq!(dataview.row_max_severity("net"), Some(Severity::Warning));
+        assert_eq!(dataview.row_max_severity("cpu"), None);
+        assert_eq!(dataview.row_max_severity("missing"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_propagate_row_severity() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("check")
+            .add_value("disk", "space", "OK")
+            .add_value("disk", "inodes", "CRITICAL")
+            .add_value("net", "link", "warning")
+            .add_value("cpu", "usage", "12%")
+            .propagate_row_severity("status")
+            .build()?;
+
+        assert_eq!(
+            dataview.to_string(),
+            "check,space,inodes,link,usage,status\n\
+             disk,OK,CRITICAL,,,CRITICAL\n\
+             net,,,warning,,WARNING\n\
+             cpu,,,,12%,UNDEFINED"
+        );
+        assert_eq!(dataview.row_max_severity("disk"), Some(Severity::Critical));
+
+        let collision = Dataview::builder()
+            .set_row_header("check")
+            .add_value("disk", "status", "OK")
+            .propagate_row_severity("status")
+            .build();
+        assert!(matches!(collision, Err(DataviewError::ColumnExists(c)) if c == "status"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_exceeds() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "cpu", "97%")
+            .add_value("web-01", "state", "running")
+            .add_value("web-02", "cpu", " 45 % ")
+            .add_value("web-02", "mem", "150MB")
+            .add_value("web-02", "latency", "12.5 ms")
+            .add_value("web-03", "state", "n/a")
+            .build()?;
+
+        assert!(dataview.row_exceeds("web-01", 90.0));
+        assert!(!dataview.row_exceeds("web-01", 97.0));
+        assert!(dataview.row_exceeds("web-02", 100.0));
+        assert!(!dataview.row_exceeds("web-02", 150.0));
+        assert!(!dataview.row_exceeds("web-03", -1.0));
+        assert!(!dataview.row_exceeds("missing", 0.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_column_adds_unit_suffix() -> Result<(), DataviewError> {
+        let mut dataview = Dataview::builder()
+            .set_row_header("disk")
+            .add_value("sda", "used", "42")
+            .add_value("sda", "mount", "/")
+            .add_value("sdb", "mount", "/data")
+            .add_value("sdc", "used", "7.5")
+            .build()?;
+
+        assert!(dataview.map_column("used", |v| format!("{v} GB")));
+
+        assert_eq!(dataview.value("sda", "used"), Some(&"42 GB".to_string()));
+        assert_eq!(dataview.value("sdb", "used"), None);
+        assert_eq!(dataview.value("sdc", "used"), Some(&"7.5 GB".to_string()));
+        assert_eq!(dataview.value("sda", "mount"), Some(&"/".to_string()));
+        assert_eq!(
+            dataview.to_string(),
+            "disk,used,mount\nsda,42 GB,/\nsdb,,/data\nsdc,7.5 GB,"
+        );
+
+        assert!(!dataview.map_column("missing", |v| v.to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_column_case_merges_columns() -> Result<(), DataviewError> {
+        let mut dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "CPU", "10")
+            .add_value("web-01", "mem", "1G")
+            .add_value("web-02", "cpu", "20")
+            .add_value("web-03", "CPU", "30")
+            .add_value("web-03", "cpu", "31")
+            .build()?;
+
+        dataview.normalize_column_case(false);
+
+        assert_eq!(dataview.column_order(), &["cpu", "mem"]);
+        assert_eq!(
+            dataview.to_string(),
+            "host,cpu,mem\nweb-01,10,1G\nweb-02,20,\nweb-03,30,"
+        );
+
+        dataview.normalize_column_case(true);
+        assert_eq!(dataview.column_order(), &["CPU", "MEM"]);
+        assert_eq!(dataview.value("web-02", "CPU"), Some(&"20".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_map_cells_redacts_matching_values() -> Result<(), DataviewError> {
+        let mut dataview = Dataview::builder()
+            .set_row_header("user")
+            .add_value("alice", "token", "tok-1234")
+            .add_value("alice", "role", "admin")
+            .add_value("bob", "note", "uses tok-9999 daily")
+            .build()?;
+
+        let mut seen = Vec::new();
+        dataview.map_cells(|row, column, value| {
+            seen.push(format!("{row}/{column}"));
+            if value.contains("tok-") {
+                "[REDACTED]".to_string()
+            } else {
+                value.to_string()
+            }
+        });
+
+        assert_eq!(seen, vec!["alice/token", "alice/role", "bob/note"]);
+        assert_eq!(
+            dataview.to_string(),
+            "user,token,role,note\nalice,[REDACTED],admin,\nbob,,,[REDACTED]"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_map_ordered_output() -> Result<(), DataviewError> {
+        let mut map = BTreeMap::new();
+        map.insert(
+            "web-02".to_string(),
+            BTreeMap::from([
+                ("status".to_string(), "down".to_string()),
+                ("cpu".to_string(), "0%".to_string()),
+            ]),
+        );
+        map.insert(
+            "web-01".to_string(),
+            BTreeMap::from([
+                ("status".to_string(), "up".to_string()),
+                ("cpu".to_string(), "12%".to_string()),
+            ]),
+        );
+
+        let dataview = from_map("host", &map)?;
+        assert_eq!(
+            dataview.to_string(),
+            "host,cpu,status\nweb-01,12%,up\nweb-02,0%,down"
+        );
+
+        assert!(matches!(
+            from_map("host", &BTreeMap::new()),
+            Err(DataviewError::MissingValue)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_hash_map_uses_comparator() -> Result<(), DataviewError> {
+        let mut map = HashMap::new();
+        for host in ["a", "b", "c"] {
+            map.insert(
+                host.to_string(),
+                HashMap::from([
+                    ("x".to_string(), format!("{host}x")),
+                    ("y".to_string(), format!("{host}y")),
+                ]),
+            );
+        }
+
+        let dataview = from_hash_map("host", &map, |a, b| b.cmp(a))?;
+        assert_eq!(dataview.row_order(), &["c", "b", "a"]);
+        assert_eq!(dataview.column_order(), &["y", "x"]);
+        assert_eq!(dataview.value("b", "x"), Some(&"bx".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_rendered_len_matches_to_string() -> Result<(), DataviewError> {
+        let views = [
+            create_basic_dataview()?,
+            DataviewBuilder::new()
+                .set_row_header("queue,id")
+                .add_headline("note", "multi\nline")
+                .add_value("<!>q1", "path", "C:\\tmp")
+                .add_value("q2", "count", "45,000")
+                .build()?,
+            DataviewBuilder::new()
+                .set_row_header("名前")
+                .add_value("行", "列", "値")
+                .build()?,
+        ];
+
+        for view in &views {
+            assert_eq!(view.rendered_len(), view.to_string().len());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_update_and_render_delta() -> Result<(), DataviewError> {
+        let before = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "up")
+            .add_value("web-01", "cpu", "10%")
+            .add_value("web-02", "status", "up")
+            .add_value("web-02", "cpu", "20%")
+            .build()?;
+
+        let mut after = before.clone();
+        after.apply_update(&[
+            ("web-02".to_string(), "cpu".to_string(), "95%".to_string()),
+            ("web-03".to_string(), "status".to_string(), "up".to_string()),
+        ])?;
+
+        // Only the updated cells differ
+        assert_eq!(after.value("web-01", "cpu"), before.value("web-01", "cpu"));
+        assert_eq!(after.value("web-02", "cpu"), Some(&"95%".to_string()));
+        assert_eq!(after.row_order(), &["web-01", "web-02", "web-03"]);
+
+        assert_eq!(
+            after.render_delta(&before)?,
+            "\
+host,status,cpu
+<!>delta,2
+web-02,up,95%
+web-03,up,"
+        );
+
+        // No changes renders the marker with a zero count
+        assert_eq!(after.render_delta(&after)?, "host,status,cpu\n<!>delta,0");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_delta_removed_rows_and_headlines() -> Result<(), DataviewError> {
+        let before = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_headline("owner", "ops")
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "status", "up")
+            .build()?;
+        let after = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "us")
+            .add_headline("owner", "ops")
+            .add_headline("zone", "a")
+            .add_value("web-01", "status", "down")
+            .build()?;
+
+        assert_eq!(
+            after.render_delta(&before)?,
+            "\
+host,status
+<!>delta,1
+<!>region,us
+<!>zone,a
+<!>deltaRemoved,web-02
+web-01,down"
+        );
+
+        // A changed row whose cells are all empty is still a data row
+        let cleared = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "")
+            .build()?;
+        assert_eq!(
+            cleared.render_delta(&before)?,
+            "host,status\n<!>delta,1\n<!>deltaRemoved,web-02\nweb-01,"
+        );
+
+        let marked = Dataview::builder()
+            .set_row_header("host")
+            .add_headline(DELTA_MARKER, "3")
+            .add_value("web-01", "status", "up")
+            .build()?;
+        assert!(matches!(
+            marked.render_delta(&before),
+            Err(DataviewError::ReservedHeadline(key)) if key == DELTA_MARKER
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_delta_uses_render_options() -> Result<(), DataviewError> {
+        let options = RenderOptions {
+            include_bom: true,
+            drop_empty_headlines: true,
+            line_ending: LineEnding::Crlf,
+            row_header_alias: Some("rowName".to_string()),
+            hide_empty_columns: true,
+        };
+        let before = Dataview::builder()
+            .set_row_header("host")
+            .render_options(options.clone())
+            .set_columns(&["status", "unused"])
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "status", "up")
+            .build()?;
+        let after = Dataview::builder()
+            .set_row_header("host")
+            .render_options(options)
+            .set_columns(&["status", "unused"])
+            .add_headline("note", "")
+            .add_value("web-01", "status", "down")
+            .build()?;
+
+        assert_eq!(
+            after.render_delta(&before)?,
+            "\u{FEFF}rowName,status\r\n<!>delta,1\r\n<!>deltaRemoved,web-02\r\nweb-01,down"
+        );
+        assert!(after.to_string().starts_with("\u{FEFF}rowName,status\r\n"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_update_sanitizes_like_builder() -> Result<(), DataviewError> {
+        let mut view = Dataview::builder()
+            .set_row_header("host")
+            .trim_values()
+            .add_value("web-01", "status", "up")
+            .build()?;
+        view.apply_update(&[(
+            "web\u{202E}-02".to_string(),
+            "sta\u{200B}tus".to_string(),
+            "  down\u{7}  ".to_string(),
+        )])?;
+        view.set_value("web-01", "status", " up ")?;
+        assert_eq!(view.to_string(), "host,status\nweb-01,up\nweb-02,down");
+
+        let mut raw = Dataview::builder()
+            .set_row_header("host")
+            .strip_unicode_controls(false)
+            .add_value("web-01", "status", "up")
+            .build()?;
+        raw.set_value("web-01", "status", "\u{1}")?;
+        assert_eq!(raw.value("web-01", "status"), Some(&"\u{1}".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_apply_update_rejects_empty_names() -> Result<(), DataviewError> {
+        let mut view = create_basic_dataview()?;
+        let original = view.clone();
+
+        let result = view.apply_update(&[
+            ("1".to_string(), "Name".to_string(), "Bob".to_string()),
+            ("".to_string(), "Name".to_string(), "x".to_string()),
+        ]);
+
+        assert!(matches!(result, Err(DataviewError::EmptyName { .. })));
+        assert_eq!(view, original);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_trim_values() -> Result<(), DataviewError> {
+        let trimmed = Dataview::builder()
+            .set_row_header("proc")
+            .trim_values()
+            .add_value("p1", "status", "  running  ")
+            .add_value("p1", "note", "\tidle\n")
+            .build()?;
+        assert_eq!(trimmed.value("p1", "status"), Some(&"running".to_string()));
+        assert_eq!(trimmed.value("p1", "note"), Some(&"idle".to_string()));
+
+        let untouched = Dataview::builder()
+            .set_row_header("proc")
+            .add_value("p1", "status", "  running  ")
+            .build()?;
+        assert_eq!(
+            untouched.value("p1", "status"),
+            Some(&"  running  ".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_columns() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "up")
+            .add_value("web-01", "notes", "")
+            .add_value("web-02", "status", "down")
+            .add_value("web-02", "owner", "")
+            .add_value("web-02", "region", "eu")
+            .build()?;
+
+        assert_eq!(dataview.empty_columns(), vec!["notes", "owner"]);
+        assert!(create_basic_dataview()?.empty_columns().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_distinct_column_values() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "status", "down")
+            .add_value("web-03", "region", "eu")
+            .add_value("web-04", "status", "up")
+            .add_value("web-05", "status", "")
+            .add_value("web-06", "status", "down")
+            .build()?;
+
+        assert_eq!(
+            dataview.distinct_column_values("status"),
+            Some(vec!["up", "down", ""])
+        );
+        assert_eq!(dataview.distinct_column_values("region"), Some(vec!["eu"]));
+        assert_eq!(dataview.distinct_column_values("missing"), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sorted_copies_leave_original_untouched() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-02", "status", "up")
+            .add_value("web-01", "region", "eu")
+            .add_value("web-03", "cpu", "10")
+            .build()?;
+
+        let by_row = dataview.sorted_rows();
+        assert_eq!(by_row.row_order(), &["web-01", "web-02", "web-03"]);
+        assert_eq!(by_row.column_order(), dataview.column_order());
+        assert_eq!(by_row.value("web-01", "region"), Some(&"eu".to_string()));
+
+        let by_column = dataview.sorted_columns();
+        assert_eq!(by_column.column_order(), &["cpu", "region", "status"]);
+        assert_eq!(by_column.row_order(), dataview.row_order());
+
+        assert_eq!(dataview.row_order(), &["web-02", "web-01", "web-03"]);
+        assert_eq!(dataview.column_order(), &["status", "region", "cpu"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_value_owned_reuses_allocation() -> Result<(), DataviewError> {
+        let value = String::from("a fairly long cell value");
+        let ptr = value.as_ptr();
+
+        let dataview = Dataview::builder()
+            .set_row_header("id")
+            .add_value_owned("r1", "c1", value)
+            .add_row(Row::new("r2").add_cell("c1", "from row"))
+            .build()?;
+
+        assert_eq!(dataview.value("r1", "c1").unwrap().as_ptr(), ptr);
+        assert_eq!(dataview.value("r2", "c1"), Some(&"from row".to_string()));
+
+        // Values that need stripping are still sanitized
+        let stripped = Dataview::builder()
+            .set_row_header("id")
+            .add_value_owned("r1", "c1", "O\u{200B}K".to_string())
+            .build()?;
+        assert_eq!(stripped.value("r1", "c1"), Some(&"OK".to_string()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_escaped_commas() -> Result<(), DataviewError> {
+        let dataview = DataviewBuilder::new()
+            .set_row_header("queue,id")
+            .add_headline("note", "a, b\\, c")
+            .add_value("q,1", "count", "45,000")
+            .add_value("q,1", "path", "C:\\dir\\")
+            .add_value("q2", "count", "1")
+            .build()?;
+
+        dataview.validate()?;
+        create_basic_dataview()?.validate()?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_fields_detects_unescaped_delimiter() {
+        assert_eq!(count_fields("a,b,c"), 3);
+        assert_eq!(count_fields("a\\,b,c"), 2);
+        assert_eq!(count_fields("a\\\\,b"), 2);
+        assert_eq!(count_fields(""), 1);
+
+        let err = DataviewError::FieldCountMismatch {
+            line: 3,
+            expected: 2,
+            found: 3,
+        };
+        assert_eq!(err.to_string(), "Rendered line 3 has 3 fields, expected 2");
+    }
+
+    #[cfg(feature = "prometheus")]
+    #[test]
+    fn test_to_prometheus() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("disk")
+            .add_value("sda", "used %", "42")
+            .add_value("sda", "mount", "/")
+            .add_value("sdb", "used %", "7.5")
+            .add_value("sd\"c\"", "used %", "n/a")
+            .add_value("sd\"c\"", "2xx", "3")
+            .build()?;
+
+        let output = dataview.to_prometheus("geneos-disk");
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines.contains(&"# TYPE geneos_disk_used__ gauge"));
+        assert!(lines.contains(&"geneos_disk_used__{row=\"sda\"} 42"));
+        assert!(lines.contains(&"geneos_disk_used__{row=\"sdb\"} 7.5"));
+        assert!(lines.contains(&"geneos_disk_2xx{row=\"sd\\\"c\\\"\"} 3"));
+        assert!(!output.contains("mount"));
+        assert!(!output.contains("n/a"));
+        assert_eq!(lines.len(), 5);
+
+        assert_eq!(
+            dataview.to_prometheus(""),
+            output.replace("geneos_disk_", "").replace("2xx", "_2xx")
+        );
+
+        // Columns that sanitize to the same name get distinct families
+        let colliding = Dataview::builder()
+            .set_row_header("disk")
+            .add_value("sda", "used %", "42")
+            .add_value("sda", "used_%", "43")
+            .add_value("sda", "used__2", "44")
+            .build()?;
+        assert_eq!(
+            colliding.to_prometheus(""),
+            "\
+# TYPE used__ gauge
+used__{row=\"sda\"} 42
+# TYPE used___2 gauge
+used___2{row=\"sda\"} 43
+# TYPE used__2 gauge
+used__2{row=\"sda\"} 44
+"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_default_row_header() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .with_default_row_header("name")
+            .add_value("web-01", "status", "up")
+            .build()?;
+        assert_eq!(dataview.to_string(), "name,status\nweb-01,up");
+
+        let explicit = Dataview::builder()
+            .set_row_header("host")
+            .with_default_row_header("name")
+            .add_value("web-01", "status", "up")
+            .build()?;
+        assert_eq!(explicit.row_header(), "host");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_managed_entity() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .set_managed_entity("old-tier")
+            .set_managed_entity("web-tier")
+            .add_value("web-01", "status", "up")
+            .build()?;
+
+        assert_eq!(MANAGED_ENTITY_HEADLINE, "_entity");
+        assert_eq!(dataview.headline("_entity"), Some(&"web-tier".to_string()));
+        assert_eq!(
+            dataview.to_string(),
+            "host,status\n<!>region,eu\n<!>_entity,web-tier\nweb-01,up"
+        );
+
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use pretty_assertions::assert_eq;
+    #[test]
+    fn test_dedup_rows() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("event")
+            .add_row_count_headline("events")
+            .add_value("e1", "level", "warn")
+            .add_value("e1", "message", "disk full")
+            .add_value("e2", "level", "warn")
+            .add_value("e2", "message", "disk full")
+            .add_value("e3", "level", "warn")
+            .add_value("e4", "level", "warn")
+            .add_value("e4", "message", "disk full")
+            .add_footer_row(Row::new("Totals").add_cell("level", "warn"))
+            .dedup_rows()
+            .build()?;
 
-    /// Helper function to create a basic dataview for testing
-    fn create_basic_dataview() -> Result<Dataview, DataviewError> {
-        DataviewBuilder::new()
-            .set_row_header("ID")
-            .add_headline("AverageAge", "30")
-            .add_value("1", "Name", "Alice")
-            .add_value("1", "Age", "30")
-            .build()
+        // e2 repeats e1; e3 differs (no message) so e4 is kept too
+        assert_eq!(dataview.row_order(), &["e1", "e3", "e4", "Totals"]);
+        assert_eq!(dataview.value("e2", "level"), None);
+        assert_eq!(dataview.headline("events"), Some(&"3".to_string()));
+
+        Ok(())
     }
 
     #[test]
-    fn test_dataview_builder_single_row() -> Result<(), DataviewError> {
-        let dataview = create_basic_dataview()?;
+    fn test_canonicalize_is_insertion_order_independent() -> Result<(), DataviewError> {
+        let first = Dataview::builder()
+            .canonicalize()
+            .set_row_header("host")
+            .add_headline("zone", "a")
+            .add_headline("region", "eu")
+            .add_value("web-02", "mem", "60")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "mem", "50")
+            .add_value("web-02", "cpu", "20")
+            .build()?;
+        let second = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "mem", "50")
+            .add_value("web-02", "cpu", "20")
+            .add_headline("region", "eu")
+            .add_value("web-01", "cpu", "10")
+            .add_headline("zone", "a")
+            .add_value("web-02", "mem", "60")
+            .canonicalize()
+            .build()?;
 
-        // Test row header
-        assert_eq!(dataview.row_header(), "ID");
+        let expected = "host,cpu,mem\n<!>region,eu\n<!>zone,a\nweb-01,10,50\nweb-02,20,60";
+        assert_eq!(first.to_string(), expected);
+        assert_eq!(second.to_string(), expected);
 
-        // Test headline
-        assert_eq!(dataview.headline("AverageAge"), Some(&"30".to_string()));
+        let with_footer = Dataview::builder()
+            .set_row_header("disk")
+            .add_footer_row(Row::new("Totals").add_cell("used", 3))
+            .add_value("sdb", "used", "2")
+            .add_value("sda", "used", "1")
+            .canonicalize()
+            .build()?;
+        assert_eq!(with_footer.row_order(), &["sda", "sdb", "Totals"]);
 
-        // Test values
-        assert_eq!(dataview.value("1", "Name"), Some(&"Alice".to_string()));
-        assert_eq!(dataview.value("1", "Age"), Some(&"30".to_string()));
+        Ok(())
+    }
 
-        // Test structure
-        assert_eq!(dataview.row_order().len(), 1);
-        assert_eq!(dataview.column_order().len(), 2);
-        assert!(dataview.column_order().contains(&"Name".to_string()));
-        assert!(dataview.column_order().contains(&"Age".to_string()));
+    #[test]
+    fn test_validate_numeric_column() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("disk")
+            .add_value("sda", "used", "97%")
+            .add_value("sdb", "used", "N/A")
+            .add_value("sdc", "used", " 1.5 GB ")
+            .add_value("sdc", "mount", "/")
+            .add_value("sdd", "mount", "/data")
+            .build()?;
+
+        assert_eq!(
+            dataview.validate_numeric_column("used"),
+            Err(vec![("sdb".to_string(), "N/A".to_string())])
+        );
+        assert_eq!(
+            dataview.validate_numeric_column("mount"),
+            Err(vec![
+                ("sdc".to_string(), "/".to_string()),
+                ("sdd".to_string(), "/data".to_string()),
+            ])
+        );
+        assert_eq!(dataview.validate_numeric_column("missing"), Ok(()));
 
         Ok(())
     }
 
     #[test]
-    fn test_dataview_display_format() -> Result<(), DataviewError> {
-        // Test basic display
-        let dataview = create_basic_dataview()?;
+    fn test_severity_transitions() -> Result<(), DataviewError> {
+        let old = Dataview::builder()
+            .set_row_header("check")
+            .add_value("disk", "status", "OK")
+            .add_value("memory", "status", "OK")
+            .add_value("swap", "status", "WARNING")
+            .add_value("uptime", "value", "12d")
+            .build()?;
+        let new = Dataview::builder()
+            .set_row_header("check")
+            .add_value("disk", "status", "CRITICAL")
+            .add_value("memory", "status", "OK")
+            .add_value("uptime", "value", "13d")
+            .add_value("cpu", "status", "WARNING")
+            .build()?;
+
+        assert_eq!(
+            severity_transitions(&old, &new),
+            vec![
+                (
+                    "disk".to_string(),
+                    Some(Severity::Ok),
+                    Some(Severity::Critical)
+                ),
+                ("cpu".to_string(), None, Some(Severity::Warning)),
+                ("swap".to_string(), Some(Severity::Warning), None),
+            ]
+        );
+        assert!(severity_transitions(&new, &new).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat() -> Result<(), DataviewError> {
+        let dataview = heartbeat("process", ("alive", "true"))?;
+
         assert_eq!(
             dataview.to_string(),
-            "\
-ID,Name,Age
-<!>AverageAge,30
-1,Alice,30"
+            "process,status\n<!>alive,true\nheartbeat,no data"
+        );
+        assert!(dataview.validate().is_ok());
+        assert_eq!(
+            DataviewBuilder::from_dataview(&dataview).build().unwrap(),
+            dataview
         );
 
-        // Test multiple rows and columns
-        let multi_row_dataview = DataviewBuilder::new()
+        assert_eq!(
+            heartbeat("pro\u{200B}cess", ("alive", "true"))?.row_header(),
+            "process"
+        );
+        assert!(matches!(
+            heartbeat("", ("alive", "true")),
+            Err(DataviewError::EmptyName { .. })
+        ));
+        assert!(matches!(
+            heartbeat("process", ("", "true")),
+            Err(DataviewError::EmptyName { .. })
+        ));
+        assert!(matches!(
+            heartbeat("status", ("alive", "true")),
+            Err(DataviewError::RowHeaderCollision(header)) if header == "status"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_render_into_reuses_buffer() -> Result<(), DataviewError> {
+        let wide = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "mem", "20")
+            .build()?;
+        let narrow = Dataview::builder()
             .set_row_header("id")
-            // Ensure that headlines appear in the order in which they were added.
-            .add_headline("Baz", "Foo")
-            .add_headline("AlertDetails", "this is red alert")
-            .add_value("001", "name", "agila")
-            .add_value("001", "status", "up")
-            .add_value("001", "Value", "97")
-            .add_value("002", "name", "lawin")
-            .add_value("002", "status", "down")
-            .add_value("002", "Value", "85")
+            .add_value("r1", "c1", "v1")
             .build()?;
 
-        let expected_output = "\
-id,name,status,Value
-<!>Baz,Foo
-<!>AlertDetails,this is red alert
-001,agila,up,97
-002,lawin,down,85";
+        let mut buf = String::new();
+        wide.render_into(&mut buf);
+        assert_eq!(buf, wide.to_string());
+        let capacity = buf.capacity();
 
-        assert_eq!(multi_row_dataview.to_string(), expected_output);
+        narrow.render_into(&mut buf);
+        assert_eq!(buf, "id,c1\nr1,v1");
+        assert_eq!(buf.capacity(), capacity);
 
         Ok(())
     }
 
     #[test]
-    fn test_special_characters_escaping() -> Result<(), DataviewError> {
-        // Test comma escaping in row header, columns, values
-        let dataview = DataviewBuilder::new()
-            .set_row_header("queue,id")
-            .add_value("queue3", "number,code", "7,331")
-            .add_value("queue3", "count", "45,000")
-            .add_value("queue3", "ratio", "0.16")
-            .add_value("queue3", "status", "online")
+    fn test_to_flat_is_rectangular() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .set_column_default("status", "unknown")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "mem", "60")
+            .add_value("web-03", "cpu", "30")
             .build()?;
 
-        let expected_output = "\
-queue\\,id,number\\,code,count,ratio,status
-queue3,7\\,331,45\\,000,0.16,online";
+        let flat = dataview.to_flat();
+        assert_eq!(flat.row_header, "host");
+        assert_eq!(flat.columns, ["cpu", "status", "mem"]);
+        assert!(flat.rows.iter().all(|(_, values)| values.len() == 3));
+        assert_eq!(
+            flat.rows,
+            vec![
+                (
+                    "web-01".to_string(),
+                    vec!["10".into(), "up".into(), "".into()]
+                ),
+                (
+                    "web-02".to_string(),
+                    vec!["".into(), "unknown".into(), "60".into()]
+                ),
+                (
+                    "web-03".to_string(),
+                    vec!["30".into(), "unknown".into(), "".into()]
+                ),
+            ]
+        );
 
-        assert_eq!(dataview.to_string(), expected_output);
+        Ok(())
+    }
 
-        // Test other special characters
-        let dataview_special = DataviewBuilder::new()
-            .set_row_header("special")
-            .add_headline("special,headline", "headline value with, comma")
-            .add_value("special_case", "state", "testing: \"quotes\" & <symbols>")
-            .add_value("special_case", "data", "multi-line\ntext")
+    #[test]
+    fn test_set_value() -> Result<(), DataviewError> {
+        let mut dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "status", "up")
             .build()?;
 
-        let output = dataview_special.to_string();
-        assert!(output.contains("special"));
-        assert!(output.contains("<!>special\\,headline,headline value with\\, comma"));
-        assert!(output.contains("testing: \"quotes\" & <symbols>"));
-        assert!(output.contains("multi-line\\ntext"));
+        dataview.set_value("web-02", "status", "down")?;
+        dataview.set_value("web-01", "latency", 12)?;
+        assert_eq!(
+            dataview.to_string(),
+            "host,status,latency\nweb-01,up,12\nweb-02,down,"
+        );
+        assert_eq!(dataview.column_order(), &["status", "latency"]);
+
+        assert!(matches!(
+            dataview.set_value("web-01", "", "x"),
+            Err(DataviewError::EmptyName { .. })
+        ));
+        assert_eq!(dataview.column_order().len(), 2);
 
         Ok(())
     }
 
     #[test]
-    fn test_empty_and_missing_values() -> Result<(), DataviewError> {
-        // Test with some missing values
-        let dataview = DataviewBuilder::new()
-            .set_row_header("item")
-            .add_value("item1", "col1", "value1")
-            .add_value("item1", "col2", "value2")
-            .add_value("item2", "col1", "value3")
-            // Deliberately missing item2/col2
-            .add_value("item3", "col3", "value4") // New column not in other rows
+    fn test_numeric_columns() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .set_columns(&["declared"])
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "latency", "12 ms")
+            .add_value("web-01", "mixed", "5")
+            .add_value("web-02", "cpu", "2.5%")
+            .add_value("web-02", "mixed", "N/A")
+            .add_value("web-02", "status", "up")
+            .build()?;
+
+        assert_eq!(dataview.numeric_columns(), vec!["cpu", "latency"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_metadata_is_not_rendered() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_metadata("source", "inventory.csv")
+            .add_metadata("collection_ms", 42)
+            .add_headline("region", "eu")
+            .add_value("web-01", "status", "up")
             .build()?;
 
+        assert_eq!(dataview.metadata().len(), 2);
+        assert_eq!(dataview.metadata()["source"], "inventory.csv");
+        assert_eq!(dataview.metadata()["collection_ms"], "42");
+
         let output = dataview.to_string();
+        assert_eq!(output, "host,status\n<!>region,eu\nweb-01,up");
+        assert!(!output.contains("inventory.csv"));
 
-        // Verify output format has empty cells where expected
-        assert!(output.contains("item1,value1,value2,"));
-        assert!(output.contains("item2,value3,,"));
-        assert!(output.contains("item3,,,value4"));
+        let rebuilt = DataviewBuilder::from_dataview(&dataview).build()?;
+        assert_eq!(rebuilt.metadata(), dataview.metadata());
 
-        // Test accessing missing values
-        assert_eq!(dataview.value("item2", "col2"), None);
-        assert_eq!(dataview.value("nonexistent", "col1"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_row_cell_counts() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .set_column_default("status", "unknown")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "mem", "20")
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "cpu", "30")
+            .add_value("web-03", "cpu", "")
+            .add_value("web-03", "mem", "5")
+            .build()?;
+
+        assert_eq!(
+            dataview.row_cell_counts(),
+            vec![("web-01", 3), ("web-02", 1), ("web-03", 1)]
+        );
 
         Ok(())
     }
 
     #[test]
-    fn test_dataview_complex() -> Result<(), DataviewError> {
-        // This test creates a more realistic Dataview with many rows, columns and headlines
-        let dataview = DataviewBuilder::new()
-            .set_row_header("cpu")
-            // Add multiple headlines
-            .add_headline("numOnlineCpus", "4")
-            .add_headline("loadAverage1Min", "0.32")
-            .add_headline("loadAverage5Min", "0.45")
-            .add_headline("loadAverage15Min", "0.38")
-            .add_headline("HyperThreadingStatus", "ENABLED")
-            // CPU average row
-            .add_value("Average_cpu", "percentUtilisation", "3.75 %")
-            .add_value("Average_cpu", "percentUserTime", "2.15 %")
-            .add_value("Average_cpu", "percentKernelTime", "1.25 %")
-            .add_value("Average_cpu", "percentIdle", "96.25 %")
-            // CPU 0 with values in all columns
-            .add_value("cpu_0", "type", "GenuineIntel Intel(R)")
-            .add_value("cpu_0", "state", "on-line")
-            .add_value("cpu_0", "clockSpeed", "2500.00 MHz")
-            .add_value("cpu_0", "percentUtilisation", "3.25 %")
-            .add_value("cpu_0", "percentUserTime", "1.95 %")
-            .add_value("cpu_0", "percentKernelTime", "1.30 %")
-            .add_value("cpu_0", "percentIdle", "96.75 %")
-            // CPU 1 with same structure
-            .add_value("cpu_1", "type", "GenuineIntel Intel(R)")
-            .add_value("cpu_1", "state", "on-line")
-            .add_value("cpu_1", "clockSpeed", "2500.00 MHz")
-            .add_value("cpu_1", "percentUtilisation", "4.25 %")
-            .add_value("cpu_1", "percentUserTime", "2.35 %")
-            .add_value("cpu_1", "percentKernelTime", "1.20 %")
-            .add_value("cpu_1", "percentIdle", "95.75 %")
-            // cpu_2 with a comma in one value (needs escaping) and some missing values
-            .add_value("cpu_2", "type", "GenuineIntel, Intel(R)")
-            .add_value("cpu_2", "state", "on-line")
-            .add_value("cpu_2", "clockSpeed", "2,500.00 MHz")
-            // Add another logical CPU
-            .add_value("cpu_0_logical#1", "type", "logical")
-            .add_value("cpu_0_logical#1", "state", "on-line")
-            .add_value("cpu_0_logical#1", "clockSpeed", "2500.00 MHz")
-            .add_value("cpu_0_logical#1", "percentUtilisation", "2.54 %")
+    fn test_join_columns() -> Result<(), DataviewError> {
+        let left = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-02", "cpu", "20")
+            .build()?;
+        let right = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "us")
+            .add_headline("source", "agent")
+            .add_value("web-02", "mem", "60")
+            .add_value("web-01", "mem", "50")
+            .add_value("web-03", "mem", "70")
             .build()?;
 
-        // Get the output
-        let output = dataview.to_string();
+        let joined = left.join_columns(&right).unwrap();
+        assert_eq!(
+            joined.to_string(),
+            "host,cpu,mem\n<!>region,eu\n<!>source,agent\nweb-01,10,50\nweb-02,20,60\nweb-03,,70"
+        );
 
-        // Check structure
-        assert_eq!(dataview.row_order().len(), 5); // 5 rows
-        assert_eq!(dataview.row_order()[0], "Average_cpu".to_string());
-        assert_eq!(dataview.row_order()[1], "cpu_0".to_string());
-        assert_eq!(dataview.row_order()[2], "cpu_1".to_string());
-        assert_eq!(dataview.row_order()[3], "cpu_2".to_string());
-        assert_eq!(dataview.row_order()[4], "cpu_0_logical#1".to_string());
+        assert_eq!(
+            left.join_columns(&left),
+            Err(MergeError::ColumnConflict("cpu".to_string()))
+        );
+        let other_header = right.with_row_header("server")?;
+        assert_eq!(
+            left.join_columns(&other_header),
+            Err(MergeError::RowHeaderMismatch {
+                left: "host".to_string(),
+                right: "server".to_string(),
+            })
+        );
 
-        assert_eq!(dataview.headlines.len(), 5); // 5 headlines
+        Ok(())
+    }
 
-        // Assert column ordering is preserved
-        let expected_columns = [
-            "percentUtilisation",
-            "percentUserTime",
-            "percentKernelTime",
-            "percentIdle",
-            "type",
-            "state",
-            "clockSpeed",
-        ];
-        for (idx, col) in expected_columns.iter().enumerate() {
-            if idx < dataview.column_order().len() {
-                assert!(dataview.column_order().contains(&col.to_string()));
+    #[test]
+    fn test_split_by_columns() -> Result<(), DataviewError> {
+        let mut builder = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu");
+        for row in ["web-01", "web-02"] {
+            for (i, col) in ["a", "b", "c", "d", "e", "f"].iter().enumerate() {
+                builder = builder.add_value(row, col, format!("{row}-{i}"));
             }
         }
+        let dataview = builder.build()?;
 
-        // Basic format checks
-        assert!(output.starts_with("cpu,"));
-        assert!(output.contains("<!>numOnlineCpus,4\n"));
-        assert!(output.contains("<!>loadAverage1Min,0.32\n"));
-        assert!(output.contains("<!>HyperThreadingStatus,ENABLED\n"));
+        let views = dataview.split_by_columns(&[&["a", "b", "c"], &["f", "e", "d", "missing"]]);
+
+        assert_eq!(views.len(), 2);
+        assert_eq!(
+            views[0].to_string(),
+            "host,a,b,c\n<!>region,eu\nweb-01,web-01-0,web-01-1,web-01-2\nweb-02,web-02-0,web-02-1,web-02-2"
+        );
+        assert_eq!(
+            views[1].to_string(),
+            "host,d,e,f\n<!>region,eu\nweb-01,web-01-3,web-01-4,web-01-5\nweb-02,web-02-3,web-02-4,web-02-5"
+        );
+        assert!(
+            dataview.split_by_columns(&[&["a"], &[]])[1]
+                .column_order()
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[cfg(feature = "syslog")]
+    #[test]
+    fn test_syslog_line() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .strip_unicode_controls(false)
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_headline("note", "two\nlines")
+            .add_headline("empty", "")
+            .add_headline("expr", "a=b")
+            .add_headline("path", "C:\\Program Files\\\"x\"")
+            .add_headline("ctl", "tab\there\u{7f}\u{85}")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-02", "cpu", "20")
+            .build()?;
 
-        // Check comma escaping
-        assert!(output.contains("GenuineIntel\\, Intel(R)"));
-        assert!(output.contains("2\\,500.00 MHz"));
+        assert_eq!(
+            syslog_line(&dataview),
+            concat!(
+                r#"host rows=2 region=eu note="two\nlines" empty="" expr="a=b""#,
+                r#" path="C:\\Program Files\\\"x\"" ctl="tab\there\x7f\x85""#,
+            )
+        );
+        assert_eq!(
+            SyslogFacility::Local0 as u8 * 8 + SyslogLevel::Warning as u8,
+            132
+        );
 
         Ok(())
     }
 
     #[test]
-    fn test_error_conditions() -> Result<(), ()> {
-        // Test missing row header
-        let result = DataviewBuilder::new()
-            .add_value("row1", "col1", "value1")
-            .build();
+    fn test_to_ndjson_lines_parse_individually() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .strip_unicode_controls(false)
+            .set_row_header("host")
+            .add_headline("region", "eu \"west\"")
+            .set_column_default("status", "unknown")
+            .add_value("web-01", "status", "up")
+            .add_value("web-01", "note", "a\nb\\c\t\u{1}")
+            .add_value("web-02", "cpu", "2,5")
+            .build()?;
 
-        assert!(matches!(result, Err(DataviewError::MissingRowHeader)));
+        let ndjson = dataview.to_ndjson();
+        assert!(ndjson.ends_with('\n'));
+        let rows: Vec<serde_json::Value> = ndjson
+            .lines()
+            .map(|line| serde_json::from_str(line).expect("each line is valid JSON"))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                serde_json::json!({
+                    "host": "web-01",
+                    "status": "up",
+                    "note": "a\nb\\c\t\u{1}",
+                }),
+                serde_json::json!({"host": "web-02", "status": "unknown", "cpu": "2,5"}),
+            ]
+        );
 
-        // Test missing values
-        let result = DataviewBuilder::new().set_row_header("header").build();
+        let with_headlines = dataview.to_ndjson_with_headlines();
+        let mut lines = with_headlines.lines();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(lines.next().unwrap()).unwrap(),
+            serde_json::json!({"headlines": {"region": "eu \"west\""}})
+        );
+        assert_eq!(
+            lines.collect::<Vec<_>>(),
+            ndjson.lines().collect::<Vec<_>>()
+        );
 
-        assert!(matches!(result, Err(DataviewError::MissingValue)));
+        Ok(())
+    }
 
-        // Ensure headlines alone are not enough
-        let result = DataviewBuilder::new()
-            .set_row_header("header")
-            .add_headline("headline1", "value1")
-            .build();
+    #[test]
+    fn test_to_markdown_escapes_pipes() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("cmd")
+            .add_headline("source", "ps|grep")
+            .add_value("p1", "args", "a | b")
+            .add_value("p1", "note", "two\nlines")
+            .add_value("p2", "args", "plain")
+            .build()?;
 
-        assert!(matches!(result, Err(DataviewError::MissingValue)));
+        let markdown = dataview.to_markdown();
+        assert_eq!(
+            markdown,
+            "\
+- source: ps\\|grep
+
+| cmd | args | note |
+| --- | --- | --- |
+| p1 | a \\| b | two<br>lines |
+| p2 | plain |  |
+"
+        );
+
+        // Every table line has the same number of unescaped pipes
+        for line in markdown.lines().filter(|l| l.starts_with('|')) {
+            let pipes = line.matches('|').count() - line.matches("\\|").count();
+            assert_eq!(pipes, 4, "malformed table line: {line}");
+        }
 
         Ok(())
     }
 
     #[test]
-    fn test_row_builder() -> Result<(), DataviewError> {
-        let row1 = Row::new("process1")
-            .add_cell("Status", "Running")
-            .add_cell("CPU", "2.5%");
-
-        let row2 = Row::new("process2")
-            .add_cell("Status", "Stopped")
-            .add_cell("CPU", "0.0%");
-
+    fn test_column_defaults_for_missing_cells() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("Process")
-            .add_row(row1)
-            .add_row(row2)
+            .set_row_header("host")
+            .set_column_default("status", "unknown")
+            .set_column_default("cpu", "n/a")
+            .add_value("web-01", "status", "up")
+            .add_value("web-01", "cpu", "10%")
+            .add_value("web-02", "mem", "2GB")
             .build()?;
 
-        let output = dataview.to_string();
-
-        assert!(output.contains("Process,Status,CPU"));
-        assert!(output.contains("process1,Running,2.5%"));
-        assert!(output.contains("process2,Stopped,0.0%"));
+        assert_eq!(
+            dataview.to_string(),
+            "host,status,cpu,mem\nweb-01,up,10%,\nweb-02,unknown,n/a,2GB"
+        );
+        // Stored data stays sparse
+        assert_eq!(dataview.value("web-02", "status"), None);
+        assert_eq!(dataview.rendered_len(), dataview.to_string().len());
 
         Ok(())
     }
 
     #[test]
-    fn test_duplicate_headline_overwrites_value_preserves_order() -> Result<(), DataviewError> {
-        let dataview = DataviewBuilder::new()
-            .set_row_header("id")
-            .add_headline("Status", "initial")
-            .add_headline("Count", "10")
-            .add_headline("Status", "updated")
-            .add_value("r1", "col", "val")
+    fn test_parse_rows_streams_rendered_output() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("queue,id")
+            .add_headline("source", "test")
+            .add_value("q1", "count", "45,000")
+            .add_value("q1", "path", "C:\\tmp\nnext")
+            .add_value("<!>q2", "count", "7")
             .build()?;
+        let rendered = format!("{dataview}\n");
 
-        // Value should be overwritten
-        assert_eq!(dataview.headline("Status"), Some(&"updated".to_string()));
-        assert_eq!(dataview.headline("Count"), Some(&"10".to_string()));
+        let mut rows = parse_rows(std::io::Cursor::new(rendered));
 
-        // Order should reflect first insertion: Status before Count
-        assert_eq!(dataview.headline_order(), &["Status", "Count"]);
+        let (row_header, columns) = rows.next().unwrap().unwrap();
+        assert_eq!(row_header, "queue,id");
+        assert_eq!(
+            columns,
+            vec![
+                ("count".to_string(), String::new()),
+                ("path".to_string(), String::new())
+            ]
+        );
 
-        // Display should use updated value in original position
-        let output = dataview.to_string();
-        let lines: Vec<&str> = output.lines().collect();
-        assert_eq!(lines[1], "<!>Status,updated");
-        assert_eq!(lines[2], "<!>Count,10");
+        let (row, cells) = rows.next().unwrap().unwrap();
+        assert_eq!(row, "q1");
+        assert_eq!(
+            cells,
+            vec![
+                ("count".to_string(), "45,000".to_string()),
+                ("path".to_string(), "C:\\tmp\nnext".to_string())
+            ]
+        );
+
+        let (row, cells) = rows.next().unwrap().unwrap();
+        assert_eq!(row, "<!>q2");
+        assert_eq!(cells[0], ("count".to_string(), "7".to_string()));
+        assert_eq!(cells[1], ("path".to_string(), String::new()));
+
+        assert!(rows.next().is_none());
+
+        let mut empty = parse_rows(std::io::Cursor::new(""));
+        assert!(matches!(empty.next(), Some(Err(ParseError::MissingHeader))));
+        assert!(empty.next().is_none());
 
         Ok(())
     }
 
     #[test]
-    fn test_duplicate_cell_overwrites_value_preserves_order() -> Result<(), DataviewError> {
-        let dataview = DataviewBuilder::new()
-            .set_row_header("id")
-            .add_value("row1", "colA", "first")
-            .add_value("row1", "colB", "other")
-            .add_value("row2", "colA", "x")
-            .add_value("row1", "colA", "second")
-            .build()?;
-
-        // Value should be overwritten
-        assert_eq!(dataview.value("row1", "colA"), Some(&"second".to_string()));
+    fn test_parse_rows_rejects_overlong_row() {
+        let input = "host,status,region\n<!>count,3\nweb-01,up,eu\nweb-02,up\nweb-03,up,eu,extra\n";
+        let mut rows = parse_rows(input.as_bytes());
+
+        rows.next().unwrap().unwrap();
+        assert_eq!(rows.next().unwrap().unwrap().1.len(), 2);
+
+        // Fewer fields than the header is fine: trailing cells are missing
+        let (row, cells) = rows.next().unwrap().unwrap();
+        assert_eq!(row, "web-02");
+        assert_eq!(cells, vec![("status".to_string(), "up".to_string())]);
+
+        let err = rows.next().unwrap().unwrap_err();
+        assert!(matches!(
+            err,
+            ParseError::ColumnCountMismatch {
+                line: 5,
+                expected: 3,
+                found: 4
+            }
+        ));
+        assert_eq!(err.to_string(), "Line 5 has 4 fields but the header has 3");
+        assert!(rows.next().is_none());
+    }
 
-        // Row and column order should reflect first insertion only (no duplicates)
-        assert_eq!(dataview.row_order(), &["row1", "row2"]);
-        assert_eq!(dataview.column_order(), &["colA", "colB"]);
+    #[test]
+    fn test_write_to_with_bom() -> Result<(), Box<dyn std::error::Error>> {
+        let dataview = create_basic_dataview()?;
 
-        // Display should use the overwritten value
-        let output = dataview.to_string();
-        assert!(output.contains("row1,second,other"));
+        let mut plain = Vec::new();
+        dataview.write_to_with(&mut plain, &RenderOptions::default())?;
+        assert!(!plain.starts_with(UTF8_BOM.as_bytes()));
+        assert_eq!(plain, dataview.to_string().into_bytes());
+
+        let mut with_bom = Vec::new();
+        dataview.write_to_with(
+            &mut with_bom,
+            &RenderOptions {
+                include_bom: true,
+                ..RenderOptions::default()
+            },
+        )?;
+        assert_eq!(&with_bom[..3], &[0xEF, 0xBB, 0xBF]);
+        assert_eq!(&with_bom[3..], plain.as_slice());
 
         Ok(())
     }
 
     #[test]
-    fn test_backslash_escaping() -> Result<(), DataviewError> {
-        let dataview = DataviewBuilder::new()
-            .set_row_header("path\\id")
-            .add_headline("dir", "C:\\Users\\test")
-            .add_value("row\\1", "col\\a", "val\\ue")
+    fn test_write_to_with_crlf() -> Result<(), Box<dyn std::error::Error>> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_value("web-01", "note", "a\r\nb")
+            .add_value("web-02", "note", "ok")
             .build()?;
 
-        let output = dataview.to_string();
-        let lines: Vec<&str> = output.lines().collect();
+        let mut out = Vec::new();
+        let options = RenderOptions {
+            line_ending: LineEnding::Crlf,
+            ..RenderOptions::default()
+        };
+        dataview.write_to_with(&mut out, &options)?;
+        assert_eq!(
+            out,
+            b"host,note\r\n<!>region,eu\r\nweb-01,a\\r\\nb\r\nweb-02,ok"
+        );
 
-        assert_eq!(lines[0], "path\\\\id,col\\\\a");
-        assert_eq!(lines[1], "<!>dir,C:\\\\Users\\\\test");
-        assert_eq!(lines[2], "row\\\\1,val\\\\ue");
+        let mut lf = Vec::new();
+        dataview.write_to_with(&mut lf, &RenderOptions::default())?;
+        assert_eq!(lf, dataview.to_string().into_bytes());
 
         Ok(())
     }
 
     #[test]
-    fn test_accessor_methods_nonexistent_keys() -> Result<(), DataviewError> {
-        let dataview = DataviewBuilder::new()
-            .set_row_header("id")
-            .add_headline("exists", "yes")
-            .add_value("row1", "col1", "val1")
+    fn test_hide_empty_columns() -> Result<(), Box<dyn std::error::Error>> {
+        let options = RenderOptions {
+            hide_empty_columns: true,
+            ..RenderOptions::default()
+        };
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .set_columns(&["cpu", "unused", "blank", "status"])
+            .set_column_default("status", "unknown")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "blank", "")
+            .add_value("web-02", "cpu", "20")
             .build()?;
 
-        assert_eq!(dataview.headline("nonexistent"), None);
-        assert_eq!(dataview.value("row1", "nonexistent"), None);
-        assert_eq!(dataview.value("nonexistent", "col1"), None);
-        assert_eq!(dataview.value("nonexistent", "nonexistent"), None);
+        let mut out = Vec::new();
+        dataview.write_to_with(&mut out, &options)?;
+        assert_eq!(
+            String::from_utf8(out)?,
+            "host,cpu,status\nweb-01,10,unknown\nweb-02,20,unknown"
+        );
+        assert_eq!(
+            dataview.to_string(),
+            "host,cpu,unused,blank,status\nweb-01,10,,,unknown\nweb-02,20,,,unknown"
+        );
+        assert_eq!(dataview.column_order().len(), 4);
 
         Ok(())
     }
 
     #[test]
-    fn test_dataview_no_headlines() -> Result<(), DataviewError> {
-        let dataview = DataviewBuilder::new()
-            .set_row_header("item")
-            .add_value("a", "x", "1")
-            .add_value("b", "x", "2")
+    fn test_row_header_alias() -> Result<(), Box<dyn std::error::Error>> {
+        let options = RenderOptions {
+            row_header_alias: Some("rowName".to_string()),
+            ..RenderOptions::default()
+        };
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .render_options(options.clone())
+            .add_value("web-01", "cpu", "10")
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(!output.contains("<!>"));
-        assert_eq!(output, "item,x\na,1\nb,2");
+        assert_eq!(dataview.to_string(), "rowName,cpu\nweb-01,10");
+        assert_eq!(dataview.row_header(), "host");
+        assert_eq!(dataview.value("web-01", "cpu"), Some(&"10".to_string()));
+
+        let mut out = Vec::new();
+        dataview.write_to_with(&mut out, &RenderOptions::default())?;
+        assert_eq!(out, b"host,cpu\nweb-01,10");
+        let mut aliased = Vec::new();
+        dataview.write_to_with(&mut aliased, &options)?;
+        assert_eq!(aliased, b"rowName,cpu\nweb-01,10");
+
+        let with_alias = |alias: &str| {
+            Dataview::builder()
+                .set_row_header("host")
+                .render_options(RenderOptions {
+                    row_header_alias: Some(alias.to_string()),
+                    ..RenderOptions::default()
+                })
+                .add_value("web-01", "cpu", "10")
+                .build()
+        };
+        assert_eq!(
+            with_alias("row\u{200B}Name")?.to_string(),
+            "rowName,cpu\nweb-01,10"
+        );
+        assert!(matches!(
+            with_alias(""),
+            Err(DataviewError::EmptyName { kind, .. }) if kind == "row header alias"
+        ));
+        assert!(matches!(
+            with_alias("cpu"),
+            Err(DataviewError::RowHeaderCollision(alias)) if alias == "cpu"
+        ));
 
         Ok(())
     }
 
     #[test]
-    fn test_golden_snapshot_representative_dataview() -> Result<(), DataviewError> {
-        let dataview = DataviewBuilder::new()
-            .set_row_header("service")
-            .add_headline("environment", "production")
-            .add_headline("region", "eu-west-1")
-            .add_value("api-gateway", "status", "running")
-            .add_value("api-gateway", "latency_ms", "12")
-            .add_value("api-gateway", "errors", "0")
-            .add_value("db-primary", "status", "running")
-            .add_value("db-primary", "latency_ms", "3")
-            .add_value("db-primary", "errors", "0")
-            .add_value("cache", "status", "degraded")
-            .add_value("cache", "latency_ms", "45")
-            .add_value("cache", "errors", "7")
+    fn test_builder_render_options_apply_to_display() -> Result<(), Box<dyn std::error::Error>> {
+        let options = RenderOptions {
+            include_bom: true,
+            drop_empty_headlines: true,
+            line_ending: LineEnding::Crlf,
+            row_header_alias: None,
+            hide_empty_columns: false,
+        };
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .render_options(options)
+            .add_headline("note", "")
+            .add_headline("region", "eu")
+            .add_value("web-01", "cpu", "10")
             .build()?;
 
-        let expected = "\
-service,status,latency_ms,errors
-<!>environment,production
-<!>region,eu-west-1
-api-gateway,running,12,0
-db-primary,running,3,0
-cache,degraded,45,7";
-
+        let expected = "\u{FEFF}host,cpu\r\n<!>region,eu\r\nweb-01,10";
         assert_eq!(dataview.to_string(), expected);
+        assert_eq!(dataview.rendered_len(), expected.len());
+
+        let mut out = Vec::new();
+        dataview.write_to(&mut out)?;
+        assert_eq!(out, expected.as_bytes());
+
+        // Per-call options override the stored ones
+        let mut plain = Vec::new();
+        dataview.write_to_with(&mut plain, &RenderOptions::default())?;
+        assert_eq!(plain, b"host,cpu\n<!>note,\n<!>region,eu\nweb-01,10");
+
+        // Checks that parse the output are unaffected by the options
+        dataview.validate()?;
+        assert!(dataview.assert_roundtrips().is_ok());
 
         Ok(())
     }
 
-    // === C1: <!> headline injection ===
-
     #[test]
-    fn test_escape_headline_prefix_in_row_name() -> Result<(), DataviewError> {
-        // A row name starting with <!> must not render as a headline
+    fn test_write_to_with_empty_headlines() -> Result<(), Box<dyn std::error::Error>> {
         let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("<!>AlertSeverity,OK", "status", "injected")
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_headline("note", "")
+            .add_headline("status", "ok")
+            .add_value("web-01", "cpu", "10")
             .build()?;
 
-        let output = dataview.to_string();
-        // The data row must NOT start with raw <!>
-        let data_lines: Vec<&str> = output.lines().filter(|l| !l.starts_with("<!>")).collect();
-        assert!(data_lines.len() >= 2, "Should have header + data row");
-        let data_row = data_lines[1];
-        assert!(
-            !data_row.starts_with("<!>"),
-            "Row name must not produce a fake headline: {data_row}"
+        let mut kept = Vec::new();
+        dataview.write_to_with(&mut kept, &RenderOptions::default())?;
+        assert_eq!(
+            String::from_utf8(kept)?,
+            "host,cpu\n<!>region,eu\n<!>note,\n<!>status,ok\nweb-01,10"
+        );
+
+        let mut dropped = Vec::new();
+        let options = RenderOptions {
+            drop_empty_headlines: true,
+            ..RenderOptions::default()
+        };
+        dataview.write_to_with(&mut dropped, &options)?;
+        assert_eq!(
+            String::from_utf8(dropped)?,
+            "host,cpu\n<!>region,eu\n<!>status,ok\nweb-01,10"
         );
-        // The escaped form should appear
-        assert!(data_row.contains("\\<!>"));
 
         Ok(())
     }
 
     #[test]
-    fn test_escape_headline_prefix_in_value() -> Result<(), DataviewError> {
-        // A value starting with <!> should be escaped
-        let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "col", "<!>Fake,headline")
-            .build()?;
+    fn test_builder_introspection() {
+        let builder = Dataview::builder();
+        assert!(!builder.is_row_header_set());
+        assert_eq!(builder.num_pending_rows(), 0);
+        assert_eq!(builder.num_pending_values(), 0);
+
+        let builder = builder
+            .set_row_header("host")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "mem", "20")
+            .add_value("web-02", "cpu", "30")
+            .add_value("web-02", "cpu", "35");
+        assert!(builder.is_row_header_set());
+        assert_eq!(builder.num_pending_rows(), 2);
+        assert_eq!(builder.num_pending_values(), 3);
+    }
 
-        let output = dataview.to_string();
-        // Value should contain escaped form
-        assert!(output.contains("\\<!>Fake"));
+    #[test]
+    fn test_with_row_header() -> Result<(), DataviewError> {
+        let dataview = create_basic_dataview()?;
+
+        let renamed = dataview.with_row_header("Person")?;
+        assert_eq!(renamed.row_header(), "Person");
+        assert_eq!(renamed.headline_order(), dataview.headline_order());
+        assert_eq!(renamed.column_order(), dataview.column_order());
+        assert_eq!(renamed.row_order(), dataview.row_order());
+        assert_eq!(
+            renamed.to_string().split_once('\n').unwrap().1,
+            dataview.to_string().split_once('\n').unwrap().1
+        );
+        assert_eq!(dataview.row_header(), "ID");
+
+        let column = dataview.column_order()[0].clone();
+        assert!(matches!(
+            dataview.with_row_header(&column),
+            Err(DataviewError::RowHeaderCollision(header)) if header == column
+        ));
+        assert!(matches!(
+            dataview.with_row_header(""),
+            Err(DataviewError::EmptyName { .. })
+        ));
+
+        // Control characters are stripped like in the builder
+        assert_eq!(
+            dataview.with_row_header("Per\u{202E}son")?.row_header(),
+            "Person"
+        );
+        assert!(matches!(
+            dataview.with_row_header("\u{200B}"),
+            Err(DataviewError::EmptyName { .. })
+        ));
 
         Ok(())
     }
 
     #[test]
-    fn test_escape_headline_prefix_in_row_header() -> Result<(), DataviewError> {
-        // Row header starting with <!> must be escaped
+    fn test_assert_roundtrips_tricky_values() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("<!>header")
-            .add_value("row1", "col", "val")
+            .set_row_header("path,name")
+            .add_headline("note", "a,b\\c")
+            .add_headline("empty", "")
+            .add_value("C:\\temp\\", "size", "1,024")
+            .add_value("C:\\temp\\", "comment", "line1\nline2")
+            .add_value("row,2", "size", "")
+            .add_value("row,2", "comment", "\\,\\")
+            .add_value("row3", "size", "3")
             .build()?;
 
-        let output = dataview.to_string();
-        let first_line = output.lines().next().unwrap();
-        assert!(
-            first_line.starts_with("\\<!>header"),
-            "Row header must escape <!>: {first_line}"
-        );
+        if let Err(e) = dataview.assert_roundtrips() {
+            panic!("round-trip failed: {e}");
+        }
 
         Ok(())
     }
 
     #[test]
-    fn test_headline_prefix_mid_string_not_escaped() {
-        // <!> only matters at string start — mid-string is harmless
-        let escaped = "some<!>text".escape_nasty_chars();
-        assert_eq!(escaped, "some<!>text");
+    fn test_set_column_type_emits_headlines() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("disk")
+            .add_headline("host", "web-01")
+            .set_column_type("used", ColumnType::Integer)
+            .set_column_type("pctUsed", ColumnType::Float)
+            .set_column_type("used", ColumnType::Percentage)
+            .add_value("sda", "used", "40")
+            .add_value("sda", "pctUsed", "12.5")
+            .build()?;
+
+        assert_eq!(
+            dataview.headline("columnType.used"),
+            Some(&"percentage".to_string())
+        );
+        assert_eq!(
+            dataview.headline("columnType.pctUsed"),
+            Some(&"float".to_string())
+        );
+        assert_eq!(
+            dataview.to_string(),
+            "disk,used,pctUsed\n\
+             <!>host,web-01\n\
+             <!>columnType.used,percentage\n\
+             <!>columnType.pctUsed,float\n\
+             sda,40,12.5"
+        );
+
+        Ok(())
     }
 
     #[test]
-    fn test_real_headlines_unaffected() -> Result<(), DataviewError> {
-        // Legitimate headlines must still render with <!> prefix
-        let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_headline("Status", "OK")
-            .add_value("r1", "c1", "v1")
-            .build()?;
+    fn test_merge_columns() -> Result<(), DataviewError> {
+        let builder = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "mem", "1G")
+            .add_value("web-01", "cpu_pct", "11")
+            .add_value("web-02", "cpu_pct", "20")
+            .add_value("web-03", "mem", "2G");
+
+        let kept = builder.clone().merge_columns("cpu", "cpu_pct").build()?;
+        assert_eq!(kept.column_order(), &["cpu", "mem"]);
+        assert_eq!(kept.value("web-01", "cpu"), Some(&"10".to_string()));
+        assert_eq!(kept.value("web-02", "cpu"), Some(&"20".to_string()));
+        assert_eq!(kept.value("web-03", "cpu"), None);
+        assert_eq!(kept.value("web-02", "cpu_pct"), None);
+
+        let overwritten = builder.merge_columns_overwrite("cpu", "cpu_pct").build()?;
+        assert_eq!(overwritten.value("web-01", "cpu"), Some(&"11".to_string()));
+        assert_eq!(
+            overwritten.to_string(),
+            "host,cpu,mem\nweb-01,11,1G\nweb-02,20,\nweb-03,,2G"
+        );
 
-        let output = dataview.to_string();
-        assert!(output.contains("<!>Status,OK"));
+        // Merging into a new column takes over the position of `from`.
+        let renamed = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "a", "1")
+            .add_value("web-01", "b", "2")
+            .merge_columns("z", "a")
+            .build()?;
+        assert_eq!(renamed.column_order(), &["z", "b"]);
+        assert_eq!(renamed.value("web-01", "z"), Some(&"1".to_string()));
 
         Ok(())
     }
 
-    // === H1: null byte passthrough ===
-
     #[test]
-    fn test_escape_null_byte() {
-        let escaped = "before\0after".escape_nasty_chars();
-        assert_eq!(escaped, "before\\0after");
-        assert!(!escaped.contains('\0'));
+    fn test_split_escaped() {
+        assert_eq!(split_escaped(r"a\,b,c", ','), vec!["a,b", "c"]);
+        assert_eq!(split_escaped(r"a\\,b", ','), vec!["a\\", "b"]);
+        assert_eq!(split_escaped(r"a\\\,b", ','), vec!["a\\,b"]);
+        assert_eq!(
+            split_escaped(r"line1\nline2\r\0", ','),
+            vec!["line1\nline2\r\0"]
+        );
+        assert_eq!(split_escaped("a,b,", ','), vec!["a", "b", ""]);
+        assert_eq!(split_escaped(",,", ','), vec!["", "", ""]);
+        assert_eq!(split_escaped("", ','), vec![""]);
+        assert_eq!(split_escaped(r"a|b\|c", '|'), vec!["a", "b|c"]);
+        assert_eq!(split_escaped("a,b|c", '|'), vec!["a,b", "c"]);
+
+        let value = "x,\\y\n<!>";
+        let escaped = value.escape_nasty_chars();
+        assert_eq!(
+            split_escaped(&format!("{escaped},z"), ','),
+            vec![value, "z"]
+        );
     }
 
     #[test]
-    fn test_null_byte_in_value() -> Result<(), DataviewError> {
-        // Null bytes are stripped by unicode sanitizer (defense in depth:
-        // escape_nasty_chars would also escape them if they got through)
-        let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "col", "legitimate\0<!>INJECTED")
+    fn test_template_shares_static_parts() -> Result<(), Box<dyn std::error::Error>> {
+        let template = DataviewTemplate::new("host")
+            .set_columns(&["status", "cpu"])
+            .add_headline("region", "eu")
+            .set_render_options(RenderOptions {
+                include_bom: true,
+                ..RenderOptions::default()
+            });
+
+        let first = template
+            .new_builder()
+            .add_value("web-01", "status", "up")
+            .add_value("web-01", "cpu", "10")
+            .build()?;
+        let second = template
+            .new_builder()
+            .add_value("web-02", "cpu", "20")
+            .add_headline("cycle", 2)
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(
-            !output.contains('\0'),
-            "Null bytes must not appear in output"
+        assert_eq!(
+            first.to_string(),
+            "\u{FEFF}host,status,cpu\n<!>region,eu\nweb-01,up,10"
         );
-        // \0 is stripped, so "legitimate" and "<!>INJECTED" are concatenated
-        assert!(output.contains("legitimate<!>INJECTED"));
+        assert_eq!(
+            second.to_string(),
+            "\u{FEFF}host,status,cpu\n<!>region,eu\n<!>cycle,2\nweb-02,,20"
+        );
+
+        let mut out = Vec::new();
+        first.write_to_with(&mut out, &template.render_options())?;
+        assert!(out.starts_with(UTF8_BOM.as_bytes()));
 
         Ok(())
     }
 
     #[test]
-    fn test_null_byte_in_row_name() -> Result<(), DataviewError> {
+    fn test_footer_rows_stay_last_after_sort() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row\01", "col", "val")
+            .set_row_header("disk")
+            .add_row_count_headline("rows")
+            .add_aggregate_headline("totalUsed", "used", Aggregate::Sum)
+            .add_footer_row(Row::new("Totals").add_cell("used", "60"))
+            .add_value("sdc", "used", "10")
+            .add_value("sda", "used", "30")
+            .add_footer_row(Row::new("Average").add_cell("used", "20"))
+            .add_value("sdb", "used", "20")
+            .sort_rows()
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(!output.contains('\0'));
+        assert_eq!(
+            dataview.row_order(),
+            &["sda", "sdb", "sdc", "Totals", "Average"]
+        );
+        assert_eq!(dataview.headline("rows"), Some(&"3".to_string()));
+        assert_eq!(dataview.headline("totalUsed"), Some(&"60".to_string()));
+        assert_eq!(dataview.value("Totals", "used"), Some(&"60".to_string()));
+
+        // A footer with the same name as a data row moves it to the bottom
+        let dataview = Dataview::builder()
+            .set_row_header("disk")
+            .add_value("b", "used", "1")
+            .add_value("a", "used", "2")
+            .add_footer_row(Row::new("a").add_cell("note", "pinned"))
+            .sort_rows()
+            .build()?;
+        assert_eq!(dataview.row_order(), &["b", "a"]);
+        assert_eq!(dataview.value("a", "used"), Some(&"2".to_string()));
 
         Ok(())
     }
 
-    // === M3: unicode control character stripping ===
-
     #[test]
-    fn test_strip_rtl_override() -> Result<(), DataviewError> {
-        // U+202E (RTL override) can make "KO" display as "OK" — must be stripped
+    fn test_header_and_footer_rows_frame_sorted_rows() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "status", "\u{202E}KO")
+            .set_row_header("disk")
+            .add_row_count_headline("rows")
+            .add_value("sdc", "used", "10")
+            .add_footer_row(Row::new("Totals").add_cell("used", "60"))
+            .add_header_row(Row::new("Average").add_cell("used", "20"))
+            .add_value("sda", "used", "30")
+            .add_header_row(Row::new("Summary").add_cell("state", "ok"))
+            .add_value("sdb", "used", "20")
+            .sort_rows()
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(
-            !output.contains('\u{202E}'),
-            "RTL override must be stripped"
+        assert_eq!(
+            dataview.row_order(),
+            &["Average", "Summary", "sda", "sdb", "sdc", "Totals"]
+        );
+        assert_eq!(dataview.headline("rows"), Some(&"3".to_string()));
+        assert_eq!(
+            dataview.to_string(),
+            "disk,used,state\n<!>rows,3\nAverage,20,\nSummary,,ok\nsda,30,\nsdb,20,\nsdc,10,\nTotals,60,"
         );
-        assert!(output.contains("KO"));
 
         Ok(())
     }
 
     #[test]
-    fn test_strip_zero_width_space() -> Result<(), DataviewError> {
-        // U+200B (zero-width space) breaks rule matching
-        let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "col", "OK\u{200B}status")
+    fn test_builder_from_dataview() -> Result<(), DataviewError> {
+        let original = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu")
+            .add_headline("owner", "ops")
+            .set_column_default("status", "unknown")
+            .add_value("web-02", "status", "up")
+            .add_value("web-01", "cpu", "10")
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(!output.contains('\u{200B}'));
-        assert!(output.contains("OKstatus"));
+        let augmented = DataviewBuilder::from_dataview(&original)
+            .add_headline("region", "us")
+            .add_value("web-03", "status", "down")
+            .add_value("web-03", "mem", "2G")
+            .build()?;
+
+        assert_eq!(
+            augmented.to_string(),
+            "host,status,cpu,mem\n<!>region,us\n<!>owner,ops\nweb-02,up,,\nweb-01,unknown,10,\nweb-03,down,,2G"
+        );
+        assert_eq!(DataviewBuilder::from_dataview(&original).build()?, original);
+
+        // Cleanup settings carry over to values added later
+        let raw = Dataview::builder()
+            .set_row_header("host")
+            .strip_unicode_controls(false)
+            .trim_values()
+            .add_value("web-01", "status", "\u{1}up")
+            .build()?;
+        let rebuilt = DataviewBuilder::from_dataview(&raw)
+            .add_value("web-02", "status", " \u{1}down ")
+            .build()?;
+        assert_eq!(
+            rebuilt.value("web-01", "status"),
+            Some(&"\u{1}up".to_string())
+        );
+        assert_eq!(
+            rebuilt.value("web-02", "status"),
+            Some(&"\u{1}down".to_string())
+        );
+        assert_eq!(DataviewBuilder::from_dataview(&raw).build()?, raw);
 
         Ok(())
     }
 
     #[test]
-    fn test_strip_bom() -> Result<(), DataviewError> {
-        // U+FEFF (BOM) at start can confuse encoding detection
-        let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "col", "\u{FEFF}value")
-            .build()?;
+    fn test_max_bytes_fails_when_over_budget() -> Result<(), DataviewError> {
+        let builder = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "status", "up")
+            .add_value("web-02", "status", "up");
+        let len = builder.clone().build()?.rendered_len();
+
+        assert!(builder.clone().max_bytes(len).build().is_ok());
+        match builder.max_bytes(len - 1).build() {
+            Err(DataviewError::TooLarge { bytes, limit }) => {
+                assert_eq!(bytes, len);
+                assert_eq!(limit, len - 1);
+            }
+            other => panic!("expected TooLarge, got {other:?}"),
+        }
 
-        let output = dataview.to_string();
-        assert!(!output.contains('\u{FEFF}'));
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_bytes_truncates_rows() -> Result<(), DataviewError> {
+        let mut builder = Dataview::builder()
+            .set_row_header("host")
+            .add_row_count_headline("rows")
+            .add_footer_row(Row::new("Totals").add_cell("status", "4 up"))
+            .budget_mode(BudgetMode::Truncate);
+        for i in 1..=4 {
+            builder = builder.add_value(&format!("web-0{i}"), "status", "up");
+        }
+
+        let dataview = builder.clone().max_bytes(72).build()?;
+        assert_eq!(dataview.rendered_len(), 72);
+        assert_eq!(
+            dataview.to_string(),
+            "host,status\n<!>rows,2\n<!>truncatedRows,2\nweb-01,up\nweb-02,up\nTotals,4 up"
+        );
+        // Header and footer rows are not counted
+        assert_eq!(dataview.row_order().len(), 3);
+
+        // Aggregates describe the rows that are left
+        let mut summed = Dataview::builder()
+            .set_row_header("host")
+            .add_aggregate_headline("total", "cpu", Aggregate::Sum)
+            .budget_mode(BudgetMode::Truncate);
+        for i in 1..=5 {
+            summed = summed.add_value(&format!("web-0{i}"), "cpu", i);
+        }
+        let summed = summed.max_bytes(60).build()?;
+        assert_eq!(
+            summed.to_string(),
+            "host,cpu\n<!>total,3\n<!>truncatedRows,3\nweb-01,1\nweb-02,2"
+        );
+
+        // Under budget: nothing is dropped and no headline is added
+        let untouched = builder.clone().max_bytes(1000).build()?;
+        assert_eq!(untouched.headline(TRUNCATED_HEADLINE), None);
+        assert_eq!(untouched.row_order().len(), 5);
+
+        // Even with every data row dropped the view does not fit
+        assert!(matches!(
+            builder.clone().max_bytes(10).build(),
+            Err(DataviewError::TooLarge { limit: 10, .. })
+        ));
+
+        // Each data row line is 10 bytes on top of the 52 that are always kept
+        for limit in 52..=80 {
+            let view = builder.clone().max_bytes(limit).build()?;
+            let expected = if limit >= 73 { 4 } else { (limit - 52) / 10 };
+            assert_eq!(view.row_order().len(), expected + 1, "limit {limit}");
+            assert!(view.rendered_len() <= limit);
+        }
+
+        // A user headline with the same key is rejected
+        assert!(matches!(
+            builder
+                .add_headline(TRUNCATED_HEADLINE, "0")
+                .max_bytes(1000)
+                .build(),
+            Err(DataviewError::ReservedHeadline(key)) if key == TRUNCATED_HEADLINE
+        ));
 
         Ok(())
     }
 
+    #[cfg(feature = "checksum")]
     #[test]
-    fn test_preserve_ascii_whitespace() -> Result<(), DataviewError> {
-        // Tab and space must survive stripping (they are useful ASCII control/whitespace)
-        let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "col", "hello\tworld here")
-            .build()?;
+    fn test_checksum_headline() -> Result<(), DataviewError> {
+        let build = |cpu: &str| {
+            Dataview::builder()
+                .set_row_header("host")
+                .add_headline("region", "eu")
+                .add_checksum_headline("checksum")
+                .add_value("web-01", "cpu", "10")
+                .add_value("web-02", "cpu", cpu)
+                .build()
+        };
 
-        let output = dataview.to_string();
-        assert!(output.contains("hello\tworld here"));
+        let first = build("20")?;
+        let checksum = first.headline("checksum").unwrap();
+        assert_eq!(checksum.len(), 64);
+        assert!(checksum.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(build("20")?.headline("checksum"), Some(checksum));
+        // Pinned so the algorithm cannot change silently between releases
+        assert_eq!(
+            checksum,
+            "3fcac1e272dae13e301dc438461251ccfbaca31857acdab8f12581e41377be53"
+        );
+        assert_ne!(build("21")?.headline("checksum"), Some(checksum));
+
+        // Moving a character between adjacent fields changes the checksum
+        let split = |a: &str, b: &str| {
+            Dataview::builder()
+                .set_row_header("id")
+                .add_checksum_headline("checksum")
+                .add_value("r1", "a", a)
+                .add_value("r1", "b", b)
+                .build()
+        };
+        assert_ne!(
+            split("xy", "z")?.headline("checksum"),
+            split("x", "yz")?.headline("checksum")
+        );
 
         Ok(())
     }
 
     #[test]
-    fn test_strip_unicode_controls_opt_out() -> Result<(), DataviewError> {
-        // When strip_unicode_controls(false), control chars pass through
+    fn test_row_count_headline() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("id")
-            .strip_unicode_controls(false)
-            .add_value("row1", "col", "\u{202E}KO")
+            .set_row_header("host")
+            .add_row_count_headline("RowCount")
+            .add_value("web-01", "cpu", "10")
+            .add_value("web-01", "mem", "1G")
+            .add_value("web-02", "cpu", "20")
+            .add_value("web-03", "mem", "2G")
+            .sort_rows()
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(
-            output.contains('\u{202E}'),
-            "RTL override should be preserved when stripping is disabled"
+        assert_eq!(
+            dataview.headline("RowCount"),
+            Some(&dataview.row_order().len().to_string())
         );
+        assert_eq!(dataview.headline("RowCount"), Some(&"3".to_string()));
+        assert_eq!(dataview.headline_order(), &["RowCount"]);
 
         Ok(())
     }
 
     #[test]
-    fn test_strip_unicode_in_headline_key_and_value() -> Result<(), DataviewError> {
+    fn test_value_owned_is_unescaped() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("id")
-            .add_headline("stat\u{200B}us", "O\u{202E}K")
-            .add_value("r1", "c1", "v1")
+            .set_row_header("path")
+            .add_value("C:\\temp", "note", "a,b\\c")
             .build()?;
 
-        let output = dataview.to_string();
-        assert!(output.contains("<!>status,OK"));
+        assert_eq!(dataview.to_string(), "path,note\nC:\\\\temp,a\\,b\\\\c");
+        assert_eq!(
+            dataview.value_owned("C:\\temp", "note"),
+            Some("a,b\\c".to_string())
+        );
+        assert_eq!(dataview.value_owned("C:\\temp", "missing"), None);
 
         Ok(())
     }
 
     #[test]
-    fn test_strip_unicode_in_row_and_column_names() -> Result<(), DataviewError> {
+    fn test_sample_period_headline() -> Result<(), DataviewError> {
         let dataview = Dataview::builder()
-            .set_row_header("i\u{FEFF}d")
-            .add_value("ro\u{200B}w1", "co\u{202E}l", "val")
+            .set_row_header("host")
+            .add_sample_period_headline("samplePeriod", Duration::from_secs(30))
+            .add_sample_period_headline("jitter", Duration::from_millis(1500))
+            .add_value("web-01", "cpu", "10")
             .build()?;
 
-        let output = dataview.to_string();
-        let first_line = output.lines().next().unwrap();
-        assert_eq!(first_line, "id,col");
+        assert_eq!(
+            dataview.to_string(),
+            "host,cpu\n<!>samplePeriod,30\n<!>jitter,1\nweb-01,10"
+        );
 
         Ok(())
     }
 
-    // === L5: empty row/column name rejection ===
-
     #[test]
-    fn test_reject_empty_row_header() {
-        let result = Dataview::builder()
-            .set_row_header("")
-            .add_value("row1", "col", "val")
-            .build();
+    fn test_style_rows_marks_critical_row() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "cpu", "42")
+            .add_value("web-02", "cpu", "97.5")
+            .add_value("web-03", "cpu", "n/a")
+            .style_rows(|row, view| {
+                match view.value(row, "cpu").and_then(|v| v.parse::<f64>().ok()) {
+                    Some(cpu) if cpu > 90.0 => Severity::Critical,
+                    Some(_) => Severity::Ok,
+                    None => Severity::Undefined,
+                }
+            })
+            .build()?;
+
+        assert_eq!(
+            dataview.value("web-01", ROW_SEVERITY_COLUMN),
+            Some(&"OK".to_string())
+        );
+        assert_eq!(
+            dataview.value("web-02", ROW_SEVERITY_COLUMN),
+            Some(&"CRITICAL".to_string())
+        );
+        assert_eq!(
+            dataview.row_max_severity("web-02"),
+            Some(Severity::Critical)
+        );
+        assert_eq!(
+            dataview.row_max_severity("web-03"),
+            Some(Severity::Undefined)
+        );
+        assert_eq!(dataview.column_order(), &["cpu", "severity"]);
 
-        assert!(matches!(result, Err(DataviewError::EmptyName(_))));
+        Ok(())
     }
 
     #[test]
-    fn test_reject_empty_row_name() {
+    fn test_style_rows_rejects_existing_severity_column() {
         let result = Dataview::builder()
-            .set_row_header("id")
-            .add_value("", "col", "val")
+            .set_row_header("host")
+            .add_value("web-01", "severity", "high")
+            .style_rows(|_, _| Severity::Ok)
             .build();
+        assert!(matches!(result, Err(DataviewError::ColumnExists(col)) if col == "severity"));
 
-        assert!(matches!(result, Err(DataviewError::EmptyName(_))));
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "severity", "high")
+            .style_rows_into("state", |_, _| Severity::Warning)
+            .build()
+            .unwrap();
+        assert_eq!(
+            dataview.value("web-01", "severity"),
+            Some(&"high".to_string())
+        );
+        assert_eq!(
+            dataview.value("web-01", "state"),
+            Some(&"WARNING".to_string())
+        );
     }
 
     #[test]
-    fn test_reject_empty_column_name() {
-        let result = Dataview::builder()
-            .set_row_header("id")
-            .add_value("row1", "", "val")
-            .build();
+    fn test_headlines_to_string() -> Result<(), DataviewError> {
+        let dataview = Dataview::builder()
+            .set_row_header("host")
+            .add_headline("region", "eu,west")
+            .add_headline("count", 2)
+            .add_value("web-01", "cpu", "10")
+            .build()?;
+
+        let full = dataview.to_string();
+        let headline_lines: Vec<&str> = full.lines().filter(|l| l.starts_with("<!>")).collect();
+        assert_eq!(dataview.headlines_to_string(), headline_lines.join("\n"));
+        assert_eq!(
+            dataview.headlines_to_string(),
+            "<!>region,eu\\,west\n<!>count,2"
+        );
+
+        let no_headlines = Dataview::builder()
+            .set_row_header("host")
+            .add_value("web-01", "cpu", "10")
+            .build()?;
+        assert_eq!(no_headlines.headlines_to_string(), "");
+
+        let with_options = Dataview::builder()
+            .set_row_header("host")
+            .render_options(RenderOptions {
+                drop_empty_headlines: true,
+                line_ending: LineEnding::Crlf,
+                ..RenderOptions::default()
+            })
+            .add_headline("region", "eu")
+            .add_headline("note", "")
+            .add_headline("count", 2)
+            .add_value("web-01", "cpu", "10")
+            .build()?;
+        assert_eq!(
+            with_options.headlines_to_string(),
+            "<!>region,eu\r\n<!>count,2"
+        );
 
-        assert!(matches!(result, Err(DataviewError::EmptyName(_))));
+        Ok(())
     }
 
     #[test]
-    fn test_reject_empty_headline_key() {
-        let result = Dataview::builder()
-            .set_row_header("id")
-            .add_headline("", "val")
-            .add_value("row1", "col", "val")
-            .build();
+    fn test_from_delimited_tsv() -> Result<(), DataviewError> {
+        let input = "queue\tdepth\tstatus\nq1\t10\tok, mostly\n\nq2\t\tstalled\n";
+        let dataview = from_delimited(input, '\t', Quoting::None)?;
+
+        let expected = Dataview::builder()
+            .set_row_header("queue")
+            .add_value("q1", "depth", "10")
+            .add_value("q1", "status", "ok, mostly")
+            .add_value("q2", "depth", "")
+            .add_value("q2", "status", "stalled")
+            .build()?;
+        assert_eq!(dataview, expected);
+
+        let piped = from_delimited("id|name\nr1|a\\|b", '|', Quoting::Backslash)?;
+        assert_eq!(piped.value("r1", "name"), Some(&"a|b".to_string()));
 
-        assert!(matches!(result, Err(DataviewError::EmptyName(_))));
+        assert!(matches!(
+            from_delimited("id\tc1\nr1\tv1\tv2", '\t', Quoting::None),
+            Err(DataviewError::FieldCountMismatch {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        ));
+
+        Ok(())
     }
 
     #[test]
-    fn test_whitespace_only_name_after_stripping() {
-        // A name that is only unicode control chars becomes empty after stripping
-        let result = Dataview::builder()
-            .set_row_header("id")
-            .add_value("\u{200B}\u{FEFF}", "col", "val")
-            .build();
+    fn test_from_delimited_double_quotes_and_headlines() -> Result<(), DataviewError> {
+        let input = "\
+host,\"status, text\"
+<!>region,\"eu, west\"
+web-01,\"up, \"\"mostly\"\"\"
+\"<!>odd\",plain
+";
+        let dataview = from_delimited(input, ',', Quoting::DoubleQuote)?;
+        assert_eq!(dataview.column_order(), &["status, text"]);
+        assert_eq!(dataview.headline("region"), Some(&"eu, west".to_string()));
+        assert_eq!(
+            dataview.value("web-01", "status, text"),
+            Some(&"up, \"mostly\"".to_string())
+        );
+        // A quoted row name is data, even if it looks like a headline
+        assert_eq!(dataview.row_order(), &["web-01", "<!>odd"]);
+        assert_eq!(split_quoted("a\"b,c", ','), vec!["a\"b", "c"]);
+
+        let rendered = from_delimited(&dataview.to_string(), ',', Quoting::Backslash)?;
+        assert_eq!(rendered.headline("region"), Some(&"eu, west".to_string()));
+
+        assert!(matches!(
+            from_delimited("id,c1\n<!>a,b,c\nr1,v1", ',', Quoting::None),
+            Err(DataviewError::FieldCountMismatch {
+                line: 2,
+                expected: 2,
+                found: 3
+            })
+        ));
 
-        assert!(matches!(result, Err(DataviewError::EmptyName(_))));
+        Ok(())
     }
 
     #[test]
-    fn test_row_sorting_methods() -> Result<(), DataviewError> {
-        // Default: insertion order preserved
-        let default = Dataview::builder()
-            .set_row_header("id")
-            .add_value("b", "col", "1")
-            .add_value("a", "col", "1")
-            .add_value("c", "col", "1")
-            .build()?;
-        assert_eq!(default.row_order(), &["b", "a", "c"]);
-
-        // sort_rows: ascending by row name
-        let sorted = Dataview::builder()
-            .set_row_header("id")
-            .add_value("b", "col", "1")
-            .add_value("a", "col", "1")
-            .add_value("c", "col", "1")
-            .sort_rows()
-            .build()?;
-        assert_eq!(sorted.row_order(), &["a", "b", "c"]);
+    fn test_builder_preview_without_row_header() {
+        let builder = Dataview::builder()
+            .add_headline("source", "test")
+            .add_value("r1", "c1", "v,1")
+            .add_value("r2", "c2", "v2");
 
-        // sort_rows_by: custom key (length)
-        let by_len = Dataview::builder()
-            .set_row_header("id")
-            .add_row(Row::new("long").add_cell("v", "1"))
-            .add_row(Row::new("mid").add_cell("v", "1"))
-            .add_row(Row::new("s").add_cell("v", "1"))
-            .sort_rows_by(|name| name.len())
-            .build()?;
-        assert_eq!(by_len.row_order(), &["s", "mid", "long"]);
+        assert_eq!(
+            builder.preview(),
+            "\
+# PREVIEW (not valid toolkit output)
+<unset>,c1,c2
+<!>source,test
+r1,v\\,1,
+r2,,v2"
+        );
 
-        // sort_rows_with: custom comparator (reverse lexicographic)
-        let reversed = Dataview::builder()
-            .set_row_header("id")
-            .add_row(Row::new("alpha").add_cell("v", "1"))
-            .add_row(Row::new("beta").add_cell("v", "1"))
-            .add_row(Row::new("gamma").add_cell("v", "1"))
-            .sort_rows_with(|a, b| b.cmp(a))
-            .build()?;
-        assert_eq!(reversed.row_order(), &["gamma", "beta", "alpha"]);
+        // Preview does not consume the builder
+        assert!(matches!(
+            builder.build(),
+            Err(DataviewError::MissingRowHeader)
+        ));
+    }
 
-        Ok(())
+    #[test]
+    fn test_builder_preview_empty() {
+        assert_eq!(
+            DataviewBuilder::new().preview(),
+            "# PREVIEW (not valid toolkit output)\n<unset>\n"
+        );
     }
 }
 
@@ -1344,10 +7432,9 @@ mod property_tests {
                 // It is escaped if it is preceded by a backslash that is NOT itself escaped.
 
                 let mut raw_commas = 0;
-                let mut chars = line.chars().peekable();
                 let mut escaped = false;
 
-                while let Some(c) = chars.next() {
+                for c in line.chars() {
                     if escaped {
                         escaped = false;
                     } else if c == '\\' {
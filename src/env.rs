@@ -1,17 +1,24 @@
 use std::env;
 use std::error::Error;
 use std::fmt;
+use std::str::FromStr;
 
 pub enum EnvError {
     VarError(env::VarError),
     IoError(std::io::Error),
     MissingSecureEnvSupport,
     #[cfg(feature = "secure-env")]
-    DecryptionFailed(String),
+    DecryptionFailed(DecryptError),
     #[cfg(feature = "secure-env")]
     MissingKeyFile,
     #[cfg(feature = "secure-env")]
     KeyFileFormatError(String),
+    #[cfg(feature = "secure-env")]
+    EnvFileFormatError(String),
+    #[cfg(feature = "secure-env")]
+    StillEncrypted,
+    #[cfg(feature = "secure-env")]
+    BlankValue,
 }
 
 impl fmt::Debug for EnvError {
@@ -21,15 +28,64 @@ impl fmt::Debug for EnvError {
             EnvError::IoError(e) => f.debug_tuple("IoError").field(e).finish(),
             EnvError::MissingSecureEnvSupport => write!(f, "MissingSecureEnvSupport"),
             #[cfg(feature = "secure-env")]
-            EnvError::DecryptionFailed(_) => write!(f, "DecryptionFailed([REDACTED])"),
+            EnvError::DecryptionFailed(e) => f.debug_tuple("DecryptionFailed").field(e).finish(),
             #[cfg(feature = "secure-env")]
             EnvError::MissingKeyFile => write!(f, "MissingKeyFile"),
             #[cfg(feature = "secure-env")]
             EnvError::KeyFileFormatError(_) => write!(f, "KeyFileFormatError([REDACTED])"),
+            #[cfg(feature = "secure-env")]
+            EnvError::EnvFileFormatError(_) => write!(f, "EnvFileFormatError([REDACTED])"),
+            #[cfg(feature = "secure-env")]
+            EnvError::StillEncrypted => write!(f, "StillEncrypted"),
+            #[cfg(feature = "secure-env")]
+            EnvError::BlankValue => write!(f, "BlankValue"),
+        }
+    }
+}
+
+/// The reason a `+encs+` value could not be decrypted.
+///
+/// None of the variants carry key material or plaintext, so they are safe to
+/// log.
+#[cfg(feature = "secure-env")]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum DecryptError {
+    /// The ciphertext after `+encs+` is not valid hex.
+    InvalidHex,
+    /// The ciphertext is empty or not a whole number of AES blocks.
+    InvalidCiphertextLength,
+    /// The key file's `key` is neither valid hex nor valid base64.
+    InvalidKeyHex,
+    /// The key file's `iv` is neither valid hex nor valid base64.
+    InvalidIvHex,
+    /// The key or IV has the wrong length for AES-256-CBC.
+    BadKeyOrIvLength,
+    /// The cipher rejected the data, typically because the key is wrong.
+    CipherError,
+    /// The decrypted bytes are not valid UTF-8.
+    InvalidUtf8,
+}
+
+#[cfg(feature = "secure-env")]
+impl fmt::Display for DecryptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecryptError::InvalidHex => write!(f, "ciphertext is not valid hex"),
+            DecryptError::InvalidCiphertextLength => {
+                write!(f, "ciphertext length not a multiple of block size")
+            }
+            DecryptError::InvalidKeyHex => write!(f, "key is neither valid hex nor base64"),
+            DecryptError::InvalidIvHex => write!(f, "iv is neither valid hex nor base64"),
+            DecryptError::BadKeyOrIvLength => write!(f, "invalid key or iv length"),
+            DecryptError::CipherError => write!(f, "cipher rejected the data (wrong key?)"),
+            DecryptError::InvalidUtf8 => write!(f, "plaintext is not valid UTF-8"),
         }
     }
 }
 
+#[cfg(feature = "secure-env")]
+impl Error for DecryptError {}
+
 impl From<env::VarError> for EnvError {
     fn from(err: env::VarError) -> Self {
         EnvError::VarError(err)
@@ -54,11 +110,25 @@ impl fmt::Display for EnvError {
                 )
             }
             #[cfg(feature = "secure-env")]
-            EnvError::DecryptionFailed(_) => write!(f, "decryption failed"),
+            EnvError::DecryptionFailed(e) => write!(f, "decryption failed: {}", e),
             #[cfg(feature = "secure-env")]
             EnvError::MissingKeyFile => write!(f, "Missing key file for decryption"),
             #[cfg(feature = "secure-env")]
             EnvError::KeyFileFormatError(msg) => write!(f, "Key file format error: {}", msg),
+            #[cfg(feature = "secure-env")]
+            EnvError::EnvFileFormatError(msg) => write!(f, "Env file format error: {}", msg),
+            #[cfg(feature = "secure-env")]
+            EnvError::StillEncrypted => write!(
+                f,
+                "Decrypted value is itself encrypted (double encryption or mis-pasted value?)"
+            ),
+            #[cfg(feature = "secure-env")]
+            EnvError::BlankValue => {
+                write!(
+                    f,
+                    "Environment variable is set but empty or whitespace-only"
+                )
+            }
         }
     }
 }
@@ -68,6 +138,8 @@ impl Error for EnvError {
         match self {
             EnvError::VarError(e) => Some(e),
             EnvError::IoError(e) => Some(e),
+            #[cfg(feature = "secure-env")]
+            EnvError::DecryptionFailed(e) => Some(e),
             _ => None,
         }
     }
@@ -113,9 +185,63 @@ pub fn get_var_or(name: &str, default: &str) -> Result<String, EnvError> {
     }
 }
 
-/// Checks if a string slice is encrypted. Encrypted values start with "+encs+".
+/// Retrieves an environment variable parsed as `T`, or `T::default()` if it
+/// is not set.
+///
+/// A value that fails to parse also falls back to `T::default()` rather than
+/// returning an error, as does a value that is not valid Unicode or is
+/// encrypted. Use [`get_var`] and parse the result yourself to tell these
+/// cases apart.
+///
+/// # Example (ignored to avoid mutating process env in doctest)
+/// ```ignore
+/// use geneos_toolkit::env::get_var_or_default;
+/// let retries: u32 = get_var_or_default("MISSING_RETRIES");
+/// assert_eq!(retries, 0);
+/// ```
+pub fn get_var_or_default<T: FromStr + Default>(name: &str) -> T {
+    get_var(name)
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or_default()
+}
+
+/// Prefix marking a Geneos encrypted value.
+pub const ENCRYPTED_PREFIX: &str = "+encs+";
+
+/// Checks if a string slice is encrypted. Encrypted values start with [`ENCRYPTED_PREFIX`].
 pub fn is_encrypted(value: &str) -> bool {
-    value.starts_with("+encs+")
+    strip_prefix(value).is_some()
+}
+
+/// Returns the ciphertext portion of an encrypted value, or `None` if the
+/// value does not start with [`ENCRYPTED_PREFIX`].
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::env::strip_prefix;
+/// assert_eq!(strip_prefix("+encs+ABCD"), Some("ABCD"));
+/// assert_eq!(strip_prefix("plain"), None);
+/// ```
+pub fn strip_prefix(value: &str) -> Option<&str> {
+    value.strip_prefix(ENCRYPTED_PREFIX)
+}
+
+/// Compares two secrets in constant time with respect to their contents.
+///
+/// Use this instead of `==` when comparing a decrypted secret against
+/// untrusted input. Only the lengths may be observable through timing.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::env::secure_eq;
+/// assert!(secure_eq("token", "token"));
+/// assert!(!secure_eq("token", "tokem"));
+/// ```
+#[cfg(feature = "secure-env")]
+pub fn secure_eq(a: &str, b: &str) -> bool {
+    use subtle::ConstantTimeEq;
+    a.as_bytes().ct_eq(b.as_bytes()).into()
 }
 
 #[cfg(test)]
@@ -139,6 +265,23 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_get_var_or_default() {
+        with_var::<_, &str, _, _>("DEFAULT_TEST_VAR", None, || {
+            assert_eq!(get_var_or_default::<u32>("DEFAULT_TEST_VAR"), 0);
+            assert_eq!(get_var_or_default::<String>("DEFAULT_TEST_VAR"), "");
+        });
+
+        with_var("DEFAULT_TEST_VAR", Some("42"), || {
+            assert_eq!(get_var_or_default::<u32>("DEFAULT_TEST_VAR"), 42);
+        });
+
+        with_var("DEFAULT_TEST_VAR", Some("forty-two"), || {
+            assert_eq!(get_var_or_default::<u32>("DEFAULT_TEST_VAR"), 0);
+            assert!(!get_var_or_default::<bool>("DEFAULT_TEST_VAR"));
+        });
+    }
+
     #[test]
     fn test_is_encrypted() {
         assert!(is_encrypted("+encs+1234567890ABCDEF"));
@@ -158,4 +301,27 @@ mod tests {
         assert!(!is_encrypted("encs+1234"));
         assert!(!is_encrypted(" +encs+1234"));
     }
+
+    #[test]
+    fn test_strip_prefix_agrees_with_is_encrypted() {
+        for value in ["+encs+ABCD", "+encs+", "+encs", "plain", "", " +encs+AB"] {
+            assert_eq!(strip_prefix(value).is_some(), is_encrypted(value));
+        }
+        assert_eq!(strip_prefix("+encs+ABCD"), Some("ABCD"));
+        assert_eq!(strip_prefix("+encs+"), Some(""));
+        assert_eq!(
+            format!("{ENCRYPTED_PREFIX}ABCD").len(),
+            ENCRYPTED_PREFIX.len() + 4
+        );
+    }
+
+    #[cfg(feature = "secure-env")]
+    #[test]
+    fn test_secure_eq() {
+        assert!(secure_eq("", ""));
+        assert!(secure_eq("s3cr3t-token", "s3cr3t-token"));
+        assert!(!secure_eq("s3cr3t-token", "s3cr3t-tokem"));
+        assert!(!secure_eq("s3cr3t-token", "s3cr3t"));
+        assert!(!secure_eq("", "x"));
+    }
 }
@@ -29,15 +29,31 @@
 /// ```
 pub mod dataview;
 pub mod env;
+pub mod severity;
 
 #[cfg(feature = "secure-env")]
 pub mod secure_env;
 
+/// Common imports for toolkit samplers.
+///
+/// `env` holds the always-available plain variable helpers; `secure_env`
+/// (behind `secure-env`) holds the only implementation of decryption and the
+/// secure variable helpers. There is a single path for each function. This
+/// prelude re-exports the common ones: `get_var`, `get_var_or`,
+/// `get_var_or_default` and `is_encrypted`, plus, with `secure-env`,
+/// `secure_eq`, `decrypt`, `encrypt`, `get_secure_var`, `get_secure_var_or`,
+/// `load_env_file` and `Zeroizing`. Import the rest, such as `KeyFile` or
+/// `get_secure_var_strict`, from their module.
 pub mod prelude {
     pub use crate::dataview::{Dataview, Row, print_result_and_exit};
-    pub use crate::env::{get_var, get_var_or, is_encrypted};
     #[cfg(feature = "secure-env")]
-    pub use crate::secure_env::{decrypt, get_secure_var, get_secure_var_or};
+    pub use crate::env::secure_eq;
+    pub use crate::env::{get_var, get_var_or, get_var_or_default, is_encrypted};
+    #[cfg(feature = "secure-env")]
+    pub use crate::secure_env::{
+        decrypt, encrypt, get_secure_var, get_secure_var_or, load_env_file,
+    };
+    pub use crate::severity::Severity;
     #[cfg(feature = "secure-env")]
     pub use zeroize::Zeroizing;
 }
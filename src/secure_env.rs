@@ -1,16 +1,26 @@
-use crate::env::{EnvError, get_var, is_encrypted};
-use cbc::Decryptor;
+use crate::env::{DecryptError, ENCRYPTED_PREFIX, EnvError, get_var, is_encrypted, strip_prefix};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use cbc::{Decryptor, Encryptor};
 use cipher::block_padding::Pkcs7;
-use cipher::{BlockDecryptMut, KeyIvInit};
+use cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
 use hex::FromHex;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, BufRead, BufReader};
+use std::thread;
+use std::time::Duration;
 use zeroize::Zeroizing;
 
 const MAX_KEY_FILE_SIZE: u64 = 1024;
+const AES_BLOCK_SIZE: usize = 16;
 
 type KeyComponents = (Zeroizing<String>, Zeroizing<String>, Zeroizing<String>);
 
+// Errors from here must never include line contents: they may hold the key
+// or iv, and the messages end up in `EnvError`'s `Display` output.
 fn parse_key_file(path: &str) -> Result<KeyComponents, EnvError> {
     let meta = fs::metadata(path)
         .map_err(|err| EnvError::IoError(io::Error::new(err.kind(), "cannot open key file")))?;
@@ -47,7 +57,13 @@ fn parse_key_file(path: &str) -> Result<KeyComponents, EnvError> {
             continue;
         }
 
-        match line.trim().split_once('=') {
+        // Trim both sides of '=' so stray `\r` from CRLF files or padding
+        // around the separator never ends up in the hex values.
+        match line
+            .trim()
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+        {
             Some(("salt", value)) => {
                 if salt.is_some() {
                     return Err(EnvError::KeyFileFormatError(
@@ -95,46 +111,250 @@ fn parse_key_file(path: &str) -> Result<KeyComponents, EnvError> {
     Ok((salt, key, iv))
 }
 
+/// Decodes a key file `key` or `iv` value, written either in hex, as Geneos
+/// does, or in standard padded base64. Hex is tried first. Base64 must be
+/// canonical, so one key has only one base64 spelling.
+fn decode_key_material(value: &str) -> Option<Zeroizing<Vec<u8>>> {
+    if let Ok(bytes) = Vec::from_hex(value) {
+        return Some(Zeroizing::new(bytes));
+    }
+    BASE64.decode(value).ok().map(Zeroizing::new)
+}
+
+/// The coarse reason a key file failed [`validate_key_file`].
+///
+/// The variants carry no key material, paths or line contents, so they are
+/// safe to log.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum KeyFileProblem {
+    /// The file is missing or cannot be read.
+    Unreadable,
+    /// The file is too large, world-readable, or not a set of `salt`, `key`
+    /// and `iv` lines.
+    Malformed,
+    /// The `key` or `iv` is neither hex nor base64 of the right length for
+    /// AES-256-CBC.
+    InvalidKeyMaterial,
+}
+
+impl fmt::Display for KeyFileProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyFileProblem::Unreadable => write!(f, "key file cannot be read"),
+            KeyFileProblem::Malformed => write!(f, "key file is malformed"),
+            KeyFileProblem::InvalidKeyMaterial => write!(f, "key file has an invalid key or iv"),
+        }
+    }
+}
+
+impl Error for KeyFileProblem {}
+
+/// Checks that `path` is a usable key file without decrypting anything.
+///
+/// Use `.is_ok()` for a plain yes/no. On failure only a coarse
+/// [`KeyFileProblem`] is returned, never the key or iv.
+pub fn validate_key_file(path: &str) -> Result<(), KeyFileProblem> {
+    let (_, key_hex, iv_hex) = parse_key_file(path).map_err(|err| match err {
+        EnvError::IoError(_) => KeyFileProblem::Unreadable,
+        _ => KeyFileProblem::Malformed,
+    })?;
+
+    let key_bytes = decode_key_material(&key_hex).ok_or(KeyFileProblem::InvalidKeyMaterial)?;
+    let iv_bytes = decode_key_material(&iv_hex).ok_or(KeyFileProblem::InvalidKeyMaterial)?;
+    if key_bytes.len() != 32 || iv_bytes.len() != AES_BLOCK_SIZE {
+        return Err(KeyFileProblem::InvalidKeyMaterial);
+    }
+    Ok(())
+}
+
+/// Returns whether the key files at `a` and `b` hold the same salt, key and
+/// iv, e.g. to detect a key rotation between two snapshots.
+///
+/// Values are compared as decoded bytes, so the same key written in hex of
+/// either case or in base64 matches. The comparison runs in constant time
+/// and never reveals the key material; a file that cannot be read or parsed
+/// is an error.
+pub fn key_files_equal(a: &str, b: &str) -> Result<bool, EnvError> {
+    use subtle::ConstantTimeEq;
+
+    let decode = |path: &str| -> Result<[Zeroizing<Vec<u8>>; 3], EnvError> {
+        let (salt, key, iv) = parse_key_file(path)?;
+        let field = |value: &str| {
+            decode_key_material(value).ok_or_else(|| {
+                EnvError::KeyFileFormatError("invalid salt, key or iv in key file".to_string())
+            })
+        };
+        Ok([field(&salt)?, field(&key)?, field(&iv)?])
+    };
+    let a = decode(a)?;
+    let b = decode(b)?;
+
+    let equal = a
+        .iter()
+        .zip(&b)
+        .fold(subtle::Choice::from(1), |equal, (a, b)| {
+            equal & a.as_slice().ct_eq(b.as_slice())
+        });
+    Ok(equal.into())
+}
+
 /// Decrypts an encrypted value using AES-256-CBC with PKCS7 padding.
 /// Values not prefixed with `+encs+` are returned unchanged.
 ///
 /// Returns `Zeroizing<String>` so the decrypted secret is automatically
 /// zeroed when dropped. Callers can still use `&str` via auto-deref.
 pub fn decrypt(value: &str, key_file: &str) -> Result<Zeroizing<String>, EnvError> {
-    if !is_encrypted(value) {
+    decrypt_with(value, || {
+        // Salt was consumed during PBKDF key derivation by Geneos Gateway;
+        // only key and IV are needed for decryption.
+        let (_, key_hex, iv_hex) = parse_key_file(key_file)?;
+
+        let mut key_bytes = decode_key_material(&key_hex)
+            .ok_or(EnvError::DecryptionFailed(DecryptError::InvalidKeyHex))?;
+        let mut iv_bytes = decode_key_material(&iv_hex)
+            .ok_or(EnvError::DecryptionFailed(DecryptError::InvalidIvHex))?;
+        Ok((
+            std::mem::take(&mut *key_bytes),
+            std::mem::take(&mut *iv_bytes),
+        ))
+    })
+}
+
+/// Decrypts an encrypted value like [`decrypt`], taking the key material
+/// from `provide_keys` instead of a key file.
+///
+/// `provide_keys` returns the raw `(key, iv)` bytes, for deployments that
+/// fetch them from a vault or KMS. It is only called once the value is known
+/// to be a well-formed `+encs+` value, and both byte vectors are zeroed after
+/// use. Values not prefixed with `+encs+` are returned unchanged.
+pub fn decrypt_with<F>(value: &str, provide_keys: F) -> Result<Zeroizing<String>, EnvError>
+where
+    F: FnOnce() -> Result<(Vec<u8>, Vec<u8>), EnvError>,
+{
+    let Some(hex) = strip_prefix(value) else {
         return Ok(Zeroizing::new(value.to_string()));
-    }
+    };
 
-    let hex = &value[6..];
     if hex.is_empty() {
-        return Err(EnvError::DecryptionFailed("decryption failed".to_string()));
+        return Err(EnvError::DecryptionFailed(
+            DecryptError::InvalidCiphertextLength,
+        ));
     }
 
-    let mut encrypted_bytes = Zeroizing::new(
-        Vec::from_hex(hex)
-            .map_err(|_| EnvError::DecryptionFailed("decryption failed".to_string()))?,
+    let encrypted_bytes = Zeroizing::new(
+        Vec::from_hex(hex).map_err(|_| EnvError::DecryptionFailed(DecryptError::InvalidHex))?,
     );
 
-    // Salt was consumed during PBKDF key derivation by Geneos Gateway;
-    // only key and IV are needed for decryption.
-    let (_, key_hex, iv_hex) = parse_key_file(key_file)?;
+    if encrypted_bytes.len() % AES_BLOCK_SIZE != 0 {
+        return Err(EnvError::DecryptionFailed(
+            DecryptError::InvalidCiphertextLength,
+        ));
+    }
 
-    let key_bytes = Zeroizing::new(
-        Vec::from_hex(&*key_hex)
-            .map_err(|_| EnvError::DecryptionFailed("decryption failed".to_string()))?,
-    );
-    let iv_bytes = Zeroizing::new(
-        Vec::from_hex(&*iv_hex)
-            .map_err(|_| EnvError::DecryptionFailed("decryption failed".to_string()))?,
+    let (key_bytes, iv_bytes) = provide_keys()?;
+    let key_bytes = Zeroizing::new(key_bytes);
+    let iv_bytes = Zeroizing::new(iv_bytes);
+
+    decrypt_bytes(encrypted_bytes, &key_bytes, &iv_bytes)
+}
+
+/// A key file parsed once, for decrypting many values without re-reading it.
+///
+/// The key and iv are zeroed on drop, and `Debug` redacts them.
+pub struct KeyFile {
+    key: Zeroizing<Vec<u8>>,
+    iv: Zeroizing<Vec<u8>>,
+}
+
+impl fmt::Debug for KeyFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "KeyFile([REDACTED])")
+    }
+}
+
+impl KeyFile {
+    /// Reads and parses the key file at `path`.
+    pub fn load(path: &str) -> Result<Self, EnvError> {
+        let (_, key_hex, iv_hex) = parse_key_file(path)?;
+        let key = decode_key_material(&key_hex)
+            .ok_or(EnvError::DecryptionFailed(DecryptError::InvalidKeyHex))?;
+        let iv = decode_key_material(&iv_hex)
+            .ok_or(EnvError::DecryptionFailed(DecryptError::InvalidIvHex))?;
+        Ok(KeyFile { key, iv })
+    }
+
+    /// Decrypts `value` like [`decrypt`], using this key file's keys.
+    pub fn decrypt(&self, value: &str) -> Result<Zeroizing<String>, EnvError> {
+        decrypt_with(value, || Ok((self.key.to_vec(), self.iv.to_vec())))
+    }
+
+    /// Retrieves an environment variable like [`get_secure_var`], using this
+    /// key file's keys.
+    pub fn get_secure_var(&self, name: &str) -> Result<Zeroizing<String>, EnvError> {
+        self.decrypt(&get_var(name)?)
+    }
+
+    /// Retrieves and decrypts each of `names`, keyed by name.
+    ///
+    /// Fails on the first variable that is missing or cannot be decrypted,
+    /// in the order of `names`; no partial results are returned.
+    pub fn get_secure_vars(
+        &self,
+        names: &[&str],
+    ) -> Result<HashMap<String, Zeroizing<String>>, EnvError> {
+        names
+            .iter()
+            .map(|name| Ok((name.to_string(), self.get_secure_var(name)?)))
+            .collect()
+    }
+}
+
+/// Decrypts a `+encs+` value whose first 16 decoded bytes are the IV.
+///
+/// For interop with tools that generate a fresh IV per message and prepend
+/// it to the ciphertext. Only the key file's `key` is used; its `iv` is
+/// ignored. Values not prefixed with `+encs+` are returned unchanged.
+pub fn decrypt_with_prepended_iv(
+    value: &str,
+    key_file: &str,
+) -> Result<Zeroizing<String>, EnvError> {
+    let Some(hex) = strip_prefix(value) else {
+        return Ok(Zeroizing::new(value.to_string()));
+    };
+
+    let mut encrypted_bytes = Zeroizing::new(
+        Vec::from_hex(hex).map_err(|_| EnvError::DecryptionFailed(DecryptError::InvalidHex))?,
     );
 
+    // The IV block plus at least one block of ciphertext.
+    if encrypted_bytes.len() < 2 * AES_BLOCK_SIZE || encrypted_bytes.len() % AES_BLOCK_SIZE != 0 {
+        return Err(EnvError::DecryptionFailed(
+            DecryptError::InvalidCiphertextLength,
+        ));
+    }
+
+    let (_, key_hex, _) = parse_key_file(key_file)?;
+
+    let key_bytes = decode_key_material(&key_hex)
+        .ok_or(EnvError::DecryptionFailed(DecryptError::InvalidKeyHex))?;
+    let ciphertext = Zeroizing::new(encrypted_bytes.split_off(AES_BLOCK_SIZE));
+
+    decrypt_bytes(ciphertext, &key_bytes, &encrypted_bytes)
+}
+
+/// Decrypts `encrypted_bytes` in place and returns the UTF-8 plaintext.
+fn decrypt_bytes(
+    mut encrypted_bytes: Zeroizing<Vec<u8>>,
+    key_bytes: &[u8],
+    iv_bytes: &[u8],
+) -> Result<Zeroizing<String>, EnvError> {
     type Aes256Cbc = Decryptor<aes::Aes256>;
 
     let len = {
-        let decrypted = Aes256Cbc::new_from_slices(&key_bytes, &iv_bytes)
-            .map_err(|_| EnvError::DecryptionFailed("decryption failed".to_string()))?
+        let decrypted = Aes256Cbc::new_from_slices(key_bytes, iv_bytes)
+            .map_err(|_| EnvError::DecryptionFailed(DecryptError::BadKeyOrIvLength))?
             .decrypt_padded_mut::<Pkcs7>(&mut encrypted_bytes)
-            .map_err(|_| EnvError::DecryptionFailed("decryption failed".to_string()))?;
+            .map_err(|_| EnvError::DecryptionFailed(DecryptError::CipherError))?;
         decrypted.len()
     };
 
@@ -145,11 +365,56 @@ pub fn decrypt(value: &str, key_file: &str) -> Result<Zeroizing<String>, EnvErro
         Ok(s) => Ok(Zeroizing::new(s)),
         Err(e) => {
             drop(Zeroizing::new(e.into_bytes()));
-            Err(EnvError::DecryptionFailed("decryption failed".to_string()))
+            Err(EnvError::DecryptionFailed(DecryptError::InvalidUtf8))
         }
     }
 }
 
+/// Letter case of the hex digits in an encrypted `+encs+` value.
+///
+/// Geneos produces upper-case hex, which is the default. Decryption accepts
+/// either case.
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq)]
+pub enum HexCase {
+    #[default]
+    Upper,
+    Lower,
+}
+
+/// Encrypts a value using AES-256-CBC with PKCS7 padding, returning it in
+/// the `+encs+` form with upper-case hex, as Geneos does.
+///
+/// Use [`encrypt_with_case`] to choose the hex letter case.
+pub fn encrypt(plaintext: &str, key_file: &str) -> Result<String, EnvError> {
+    encrypt_with_case(plaintext, key_file, HexCase::default())
+}
+
+/// Encrypts a value like [`encrypt`], writing the hex digits in `case`.
+pub fn encrypt_with_case(
+    plaintext: &str,
+    key_file: &str,
+    case: HexCase,
+) -> Result<String, EnvError> {
+    let (_, key_hex, iv_hex) = parse_key_file(key_file)?;
+
+    let key_bytes = decode_key_material(&key_hex)
+        .ok_or_else(|| EnvError::KeyFileFormatError("invalid key or iv in key file".to_string()))?;
+    let iv_bytes = decode_key_material(&iv_hex)
+        .ok_or_else(|| EnvError::KeyFileFormatError("invalid key or iv in key file".to_string()))?;
+
+    type Aes256CbcEnc = Encryptor<aes::Aes256>;
+
+    let encrypted = Aes256CbcEnc::new_from_slices(&key_bytes, &iv_bytes)
+        .map_err(|_| EnvError::KeyFileFormatError("invalid key or iv in key file".to_string()))?
+        .encrypt_padded_vec_mut::<Pkcs7>(plaintext.as_bytes());
+
+    let hex = match case {
+        HexCase::Upper => hex::encode_upper(&encrypted),
+        HexCase::Lower => hex::encode(&encrypted),
+    };
+    Ok(format!("{ENCRYPTED_PREFIX}{hex}"))
+}
+
 /// Retrieves an environment variable and decrypts it if it is encrypted.
 ///
 /// Returns `Zeroizing<String>` — the value is zeroed on drop whether or
@@ -163,6 +428,37 @@ pub fn get_secure_var(name: &str, key_file: &str) -> Result<Zeroizing<String>, E
     }
 }
 
+/// Retrieves a secure environment variable like [`get_secure_var`], but
+/// rejects values that are almost always a misconfiguration:
+///
+/// - `EnvError::BlankValue` if the variable is set but its value, after
+///   decryption, is empty or whitespace-only.
+/// - `EnvError::StillEncrypted` if the decrypted value still starts with
+///   `+encs+`, meaning it was encrypted twice or a ciphertext was pasted in
+///   place of the plaintext.
+pub fn get_secure_var_strict(name: &str, key_file: &str) -> Result<Zeroizing<String>, EnvError> {
+    let value = get_secure_var(name, key_file)?;
+    if value.trim().is_empty() {
+        return Err(EnvError::BlankValue);
+    }
+    if is_encrypted(&value) {
+        return Err(EnvError::StillEncrypted);
+    }
+    Ok(value)
+}
+
+/// Retrieves a secure environment variable like [`get_secure_var`], for
+/// secrets the process cannot start without.
+///
+/// # Panics
+///
+/// Panics if the variable is missing or cannot be decrypted. The message
+/// names the variable and the underlying error, never the value.
+pub fn expect_secure_var(name: &str, key_file: &str) -> Zeroizing<String> {
+    get_secure_var(name, key_file)
+        .unwrap_or_else(|err| panic!("required secure variable {name} is unavailable: {err}"))
+}
+
 /// Retrieves a secure environment variable, returning a default if it is missing.
 ///
 /// Returns `Zeroizing<String>` — the value is zeroed on drop whether or
@@ -187,6 +483,168 @@ pub fn get_secure_var_or(
     }
 }
 
+/// Retrieves `primary` like [`get_secure_var`], falling back to `fallback`
+/// when `primary` is not set, for migrations where a value may live under
+/// either name.
+///
+/// Either variable is decrypted if it is encrypted. Only a missing `primary`
+/// triggers the fallback; any other error is returned as is. If both are
+/// missing, the error is the fallback's `EnvError::VarError`.
+pub fn get_secure_var_with_fallback(
+    primary: &str,
+    fallback: &str,
+    key_file: &str,
+) -> Result<Zeroizing<String>, EnvError> {
+    match get_secure_var(primary, key_file) {
+        Err(EnvError::VarError(std::env::VarError::NotPresent)) => {
+            get_secure_var(fallback, key_file)
+        }
+        result => result,
+    }
+}
+
+/// Retrieves a secure environment variable and returns only the length of
+/// its decrypted value, in characters.
+///
+/// Useful for logging that a secret was loaded without logging the secret.
+/// The plaintext is zeroed before this function returns.
+pub fn get_secure_var_len(name: &str, key_file: &str) -> Result<usize, EnvError> {
+    let value = get_secure_var(name, key_file)?;
+    Ok(value.chars().count())
+}
+
+/// Audit metadata about a secure environment variable, returned by
+/// [`describe_secure_var`].
+///
+/// `Debug` redacts `value`, so the struct is safe to log as a whole.
+pub struct SecureVarInfo {
+    /// Whether the raw value was `+encs+` encrypted.
+    pub encrypted: bool,
+    /// Length of the plaintext, in characters.
+    pub len: usize,
+    /// The plaintext, only set by [`describe_secure_var_with_value`].
+    pub value: Option<Zeroizing<String>>,
+}
+
+impl fmt::Debug for SecureVarInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SecureVarInfo")
+            .field("encrypted", &self.encrypted)
+            .field("len", &self.len)
+            .field("value", &self.value.as_ref().map(|_| "[REDACTED]"))
+            .finish()
+    }
+}
+
+/// Describes a secure environment variable without exposing it: whether it
+/// was encrypted and the length of its plaintext.
+///
+/// Useful for audit logging during a migration to encrypted values. The
+/// plaintext is zeroed before this function returns; use
+/// [`describe_secure_var_with_value`] to keep it.
+pub fn describe_secure_var(name: &str, key_file: &str) -> Result<SecureVarInfo, EnvError> {
+    let mut info = describe_secure_var_with_value(name, key_file)?;
+    info.value = None;
+    Ok(info)
+}
+
+/// Describes a secure environment variable like [`describe_secure_var`],
+/// and also returns its decrypted value.
+pub fn describe_secure_var_with_value(
+    name: &str,
+    key_file: &str,
+) -> Result<SecureVarInfo, EnvError> {
+    let raw = Zeroizing::new(get_var(name)?);
+    let encrypted = is_encrypted(&raw);
+    let value = if encrypted {
+        decrypt(&raw, key_file)?
+    } else {
+        raw
+    };
+    Ok(SecureVarInfo {
+        encrypted,
+        len: value.chars().count(),
+        value: Some(value),
+    })
+}
+
+/// Retrieves a secure environment variable like [`get_secure_var`], retrying
+/// when the key file cannot be read.
+///
+/// Only `EnvError::IoError` is retried, up to `retries` more times with
+/// `delay` between attempts; the last error is returned if all fail. Format,
+/// decryption and missing variable errors are returned immediately. Useful
+/// for key files on network mounts that may be briefly unavailable at startup.
+pub fn get_secure_var_with_retry(
+    name: &str,
+    key_file: &str,
+    retries: u32,
+    delay: Duration,
+) -> Result<Zeroizing<String>, EnvError> {
+    let mut attempt = 0;
+    loop {
+        match get_secure_var(name, key_file) {
+            Err(EnvError::IoError(_)) if attempt < retries => {
+                attempt += 1;
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Loads a `.env`-style file of `NAME=value` lines, decrypting `+encs+` values.
+///
+/// Blank lines and lines starting with `#` are skipped; names and values are
+/// trimmed. A later line for the same name replaces an earlier one. Values
+/// are returned as `Zeroizing<String>`, like the other secure helpers.
+pub fn load_env_file(
+    path: &str,
+    key_file: &str,
+) -> Result<HashMap<String, Zeroizing<String>>, EnvError> {
+    let contents =
+        Zeroizing::new(fs::read_to_string(path).map_err(|err| {
+            EnvError::IoError(io::Error::new(err.kind(), "cannot open env file"))
+        })?);
+
+    let mut vars = HashMap::new();
+    for (line_num, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (name, value) = line
+            .split_once('=')
+            .map(|(name, value)| (name.trim(), value.trim()))
+            .filter(|(name, _)| !name.is_empty())
+            .ok_or_else(|| {
+                EnvError::EnvFileFormatError(format!("invalid line {} in env file", line_num + 1))
+            })?;
+
+        vars.insert(name.to_string(), decrypt(value, key_file)?);
+    }
+    Ok(vars)
+}
+
+/// Loads a `.env`-style file like [`load_env_file`] and sets every variable
+/// in the process environment.
+///
+/// Nothing is set unless the whole file loads and decrypts successfully.
+///
+/// # Safety
+///
+/// This calls [`std::env::set_var`], so the same rules apply: it must not be
+/// called while other threads may read or write the environment.
+pub unsafe fn load_env_file_into_env(path: &str, key_file: &str) -> Result<(), EnvError> {
+    let vars = load_env_file(path, key_file)?;
+    for (name, value) in &vars {
+        // SAFETY: upheld by the caller, see the function's safety section.
+        unsafe { std::env::set_var(name, value.as_str()) };
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -288,6 +746,278 @@ iv=472A3557ADDD2525AD4E555738636A67
         });
     }
 
+    #[test]
+    fn test_decrypt_with_prepended_iv() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let key_file = key_file_path.to_str().unwrap();
+
+        // A per-message IV that differs from the key file's iv.
+        let key = Vec::from_hex("26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC")
+            .unwrap();
+        let iv = Vec::from_hex("000102030405060708090A0B0C0D0E0F").unwrap();
+        let ciphertext = Encryptor::<aes::Aes256>::new_from_slices(&key, &iv)
+            .unwrap()
+            .encrypt_padded_vec_mut::<Pkcs7>(b"per-message secret");
+        let value = format!(
+            "{ENCRYPTED_PREFIX}{}{}",
+            hex::encode_upper(&iv),
+            hex::encode_upper(&ciphertext)
+        );
+
+        assert_eq!(
+            &*decrypt_with_prepended_iv(&value, key_file).unwrap(),
+            "per-message secret"
+        );
+        assert_eq!(
+            &*decrypt_with_prepended_iv("plain", key_file).unwrap(),
+            "plain"
+        );
+
+        // The IV alone, with no ciphertext block, is rejected.
+        let iv_only = format!("{ENCRYPTED_PREFIX}{}", hex::encode_upper(&iv));
+        assert!(matches!(
+            decrypt_with_prepended_iv(&iv_only, key_file),
+            Err(EnvError::DecryptionFailed(
+                DecryptError::InvalidCiphertextLength
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_get_secure_var_with_retry() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        let kf = key_file_path.to_str().unwrap();
+
+        with_var("RETRY_VAR", Some(ENCRYPTED_VAR_1), || {
+            assert!(matches!(
+                get_secure_var_with_retry("RETRY_VAR", kf, 2, Duration::from_millis(1)),
+                Err(EnvError::IoError(_))
+            ));
+
+            // Write the key file elsewhere and move it into place so it never
+            // appears with default (world-readable) permissions.
+            let staged = dir.path().join("staged-key-file");
+            write_key_file(&staged, VALID_KEY_FILE_CONTENTS);
+            let target = key_file_path.clone();
+            let writer = thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                std::fs::rename(staged, target).unwrap();
+            });
+
+            let result = get_secure_var_with_retry("RETRY_VAR", kf, 200, Duration::from_millis(10));
+            writer.join().unwrap();
+            assert_eq!(&*result.unwrap(), DECRYPTED_VAR_1);
+        });
+
+        // Non-I/O errors are returned without retrying.
+        write_key_file(&key_file_path, "salt=00\nkey=00\niv=00");
+        with_var("RETRY_VAR", Some(ENCRYPTED_VAR_1), || {
+            let start = std::time::Instant::now();
+            assert!(matches!(
+                get_secure_var_with_retry("RETRY_VAR", kf, 5, Duration::from_secs(1)),
+                Err(EnvError::DecryptionFailed(_))
+            ));
+            assert!(start.elapsed() < Duration::from_secs(1));
+        });
+    }
+
+    #[test]
+    fn test_get_secure_var_strict_rejects_double_encryption() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        let double = encrypt(ENCRYPTED_VAR_1, kf).unwrap();
+        with_var("DOUBLE_VAR", Some(double.as_str()), || {
+            assert_eq!(&*get_secure_var("DOUBLE_VAR", kf).unwrap(), ENCRYPTED_VAR_1);
+            assert!(matches!(
+                get_secure_var_strict("DOUBLE_VAR", kf),
+                Err(EnvError::StillEncrypted)
+            ));
+        });
+
+        with_var("SINGLE_VAR", Some(ENCRYPTED_VAR_1), || {
+            assert_eq!(
+                &*get_secure_var_strict("SINGLE_VAR", kf).unwrap(),
+                DECRYPTED_VAR_1
+            );
+        });
+    }
+
+    #[test]
+    fn test_get_secure_var_strict_rejects_blank_values() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        for blank in ["", "  \t"] {
+            with_var("BLANK_VAR", Some(blank), || {
+                assert_eq!(get_secure_var("BLANK_VAR", kf).unwrap().as_str(), blank);
+                assert!(matches!(
+                    get_secure_var_strict("BLANK_VAR", kf),
+                    Err(EnvError::BlankValue)
+                ));
+            });
+        }
+
+        let encrypted_blank = encrypt(" ", kf).unwrap();
+        with_var("BLANK_VAR", Some(encrypted_blank.as_str()), || {
+            assert!(matches!(
+                get_secure_var_strict("BLANK_VAR", kf),
+                Err(EnvError::BlankValue)
+            ));
+        });
+    }
+
+    #[test]
+    fn test_expect_secure_var() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        with_var("EXPECT_VAR", Some(ENCRYPTED_VAR_1), || {
+            assert_eq!(&*expect_secure_var("EXPECT_VAR", kf), DECRYPTED_VAR_1);
+        });
+
+        with_var::<_, &str, _, _>("EXPECT_VAR", None, || {
+            let panic = std::panic::catch_unwind(|| expect_secure_var("EXPECT_VAR", kf))
+                .expect_err("missing variable should panic");
+            let message = panic.downcast_ref::<String>().unwrap();
+            assert!(message.contains("EXPECT_VAR"));
+            assert!(message.contains("environment variable not found"));
+        });
+    }
+
+    #[test]
+    fn test_get_secure_var_with_fallback() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        temp_env::with_vars([("ENC_X", None), ("PLAIN_X", Some("plain-value"))], || {
+            assert_eq!(
+                get_secure_var_with_fallback("ENC_X", "PLAIN_X", kf)
+                    .unwrap()
+                    .as_str(),
+                "plain-value"
+            );
+        });
+
+        temp_env::with_vars(
+            [
+                ("ENC_X", Some(ENCRYPTED_VAR_1)),
+                ("PLAIN_X", Some("plain-value")),
+            ],
+            || {
+                assert_eq!(
+                    get_secure_var_with_fallback("ENC_X", "PLAIN_X", kf)
+                        .unwrap()
+                        .as_str(),
+                    DECRYPTED_VAR_1
+                );
+            },
+        );
+
+        temp_env::with_vars([("ENC_X", None::<&str>), ("PLAIN_X", None)], || {
+            assert!(matches!(
+                get_secure_var_with_fallback("ENC_X", "PLAIN_X", kf),
+                Err(EnvError::VarError(std::env::VarError::NotPresent))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_get_secure_var_len() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        with_var("SECRET_LEN_VAR", Some(ENCRYPTED_VAR_1), || {
+            assert_eq!(
+                get_secure_var_len("SECRET_LEN_VAR", kf).unwrap(),
+                DECRYPTED_VAR_1.len()
+            );
+        });
+
+        with_var("SECRET_LEN_VAR", Some("naïve"), || {
+            assert_eq!(get_secure_var_len("SECRET_LEN_VAR", kf).unwrap(), 5);
+        });
+
+        with_var::<_, &str, _, _>("SECRET_LEN_VAR", None, || {
+            assert!(matches!(
+                get_secure_var_len("SECRET_LEN_VAR", kf),
+                Err(EnvError::VarError(_))
+            ));
+        });
+    }
+
+    #[test]
+    fn test_key_file_get_secure_vars() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let key_file = KeyFile::load(key_file_path.to_str().unwrap()).unwrap();
+        assert_eq!(format!("{key_file:?}"), "KeyFile([REDACTED])");
+
+        temp_env::with_vars(
+            [
+                ("BATCH_VAR_1", Some(ENCRYPTED_VAR_1)),
+                ("BATCH_VAR_2", Some(ENCRYPTED_VAR_2)),
+                ("BATCH_VAR_MISSING", None),
+            ],
+            || {
+                let vars = key_file
+                    .get_secure_vars(&["BATCH_VAR_1", "BATCH_VAR_2"])
+                    .unwrap();
+                assert_eq!(vars.len(), 2);
+                assert_eq!(vars["BATCH_VAR_1"].as_str(), DECRYPTED_VAR_1);
+                assert_eq!(vars["BATCH_VAR_2"].as_str(), DECRYPTED_VAR_2);
+
+                assert!(matches!(
+                    key_file.get_secure_vars(&["BATCH_VAR_1", "BATCH_VAR_MISSING"]),
+                    Err(EnvError::VarError(_))
+                ));
+            },
+        );
+    }
+
+    #[test]
+    fn test_describe_secure_var() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        with_var("DESCRIBE_VAR", Some(ENCRYPTED_VAR_1), || {
+            let info = describe_secure_var("DESCRIBE_VAR", kf).unwrap();
+            assert!(info.encrypted);
+            assert_eq!(info.len, DECRYPTED_VAR_1.len());
+            assert!(info.value.is_none());
+            assert!(!format!("{info:?}").contains(DECRYPTED_VAR_1));
+
+            let info = describe_secure_var_with_value("DESCRIBE_VAR", kf).unwrap();
+            assert_eq!(
+                info.value.as_deref().map(String::as_str),
+                Some(DECRYPTED_VAR_1)
+            );
+            assert!(!format!("{info:?}").contains(DECRYPTED_VAR_1));
+        });
+
+        with_var("DESCRIBE_VAR", Some("plain"), || {
+            let info = describe_secure_var("DESCRIBE_VAR", kf).unwrap();
+            assert!(!info.encrypted);
+            assert_eq!(info.len, 5);
+        });
+    }
+
     #[test]
     fn test_get_secure_var_or() {
         let dir = tempdir().unwrap();
@@ -358,6 +1088,44 @@ iv=472A3557ADDD2525AD4E555738636A67
         assert_eq!(&*decrypt(ENCRYPTED_VAR_2, kf).unwrap(), DECRYPTED_VAR_2);
     }
 
+    #[test]
+    fn test_decrypt_with_key_provider() {
+        let keys = || {
+            Ok((
+                Vec::from_hex("26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC")
+                    .unwrap(),
+                Vec::from_hex("472A3557ADDD2525AD4E555738636A67").unwrap(),
+            ))
+        };
+
+        assert_eq!(
+            &*decrypt_with(ENCRYPTED_VAR_1, keys).unwrap(),
+            DECRYPTED_VAR_1
+        );
+        assert_eq!(
+            &*decrypt_with(ENCRYPTED_VAR_2, keys).unwrap(),
+            DECRYPTED_VAR_2
+        );
+
+        // The provider is not consulted for plain or malformed values
+        let unused = || -> Result<(Vec<u8>, Vec<u8>), EnvError> { panic!("keys requested") };
+        assert_eq!(&*decrypt_with("plain", unused).unwrap(), "plain");
+        assert!(matches!(
+            decrypt_with("+encs+XYZ", unused),
+            Err(EnvError::DecryptionFailed(DecryptError::InvalidHex))
+        ));
+
+        // Provider errors are passed through
+        assert!(matches!(
+            decrypt_with(ENCRYPTED_VAR_1, || Err(EnvError::MissingKeyFile)),
+            Err(EnvError::MissingKeyFile)
+        ));
+        assert!(matches!(
+            decrypt_with(ENCRYPTED_VAR_1, || Ok((vec![0; 16], vec![0; 16]))),
+            Err(EnvError::DecryptionFailed(DecryptError::BadKeyOrIvLength))
+        ));
+    }
+
     #[test]
     fn test_decrypt_passthrough_short_values() {
         // Values shorter than 6 chars skip decryption regardless of content
@@ -371,6 +1139,74 @@ iv=472A3557ADDD2525AD4E555738636A67
         assert_eq!(&*decrypt("12345", kf).unwrap(), "12345");
     }
 
+    #[test]
+    fn test_base64_key_file() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(
+            &key_file_path,
+            "salt=89A6A795C9CCECB5\nkey=Jtbt1ToK+o+hqj+80v/yoL9ICaTgRRH2KfxzLCpCqPw=\niv=Ryo1V63dJSWtTlVXOGNqZw==",
+        );
+        let kf = key_file_path.to_str().unwrap();
+
+        assert_eq!(&*decrypt(ENCRYPTED_VAR_1, kf).unwrap(), DECRYPTED_VAR_1);
+        assert_eq!(&*decrypt(ENCRYPTED_VAR_2, kf).unwrap(), DECRYPTED_VAR_2);
+        assert_eq!(encrypt(DECRYPTED_VAR_1, kf).unwrap(), ENCRYPTED_VAR_1);
+        assert_eq!(validate_key_file(kf), Ok(()));
+
+        let bad_path = dir.path().join("bad-key-file");
+        write_key_file(
+            &bad_path,
+            "salt=89A6A795C9CCECB5\nkey=Jtbt1ToK+o+hqj+80v/y!L9ICaTgRRH2KfxzLCpCqPw=\niv=Ryo1V63dJSWtTlVXOGNqZw==",
+        );
+        assert!(matches!(
+            decrypt(ENCRYPTED_VAR_1, bad_path.to_str().unwrap()),
+            Err(EnvError::DecryptionFailed(DecryptError::InvalidKeyHex))
+        ));
+
+        // Non-zero trailing bits would decode to the same iv, so are rejected
+        let non_canonical = dir.path().join("non-canonical-key-file");
+        write_key_file(
+            &non_canonical,
+            "salt=89A6A795C9CCECB5\nkey=Jtbt1ToK+o+hqj+80v/yoL9ICaTgRRH2KfxzLCpCqPw=\niv=Ryo1V63dJSWtTlVXOGNqZx==",
+        );
+        assert_eq!(
+            validate_key_file(non_canonical.to_str().unwrap()),
+            Err(KeyFileProblem::InvalidKeyMaterial)
+        );
+    }
+
+    #[test]
+    fn test_key_files_equal() {
+        let dir = tempdir().unwrap();
+        let path = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            write_key_file(&path, contents);
+            path.to_str().unwrap().to_string()
+        };
+
+        let original = path("original", VALID_KEY_FILE_CONTENTS);
+        let copy = path("copy", &VALID_KEY_FILE_CONTENTS.to_lowercase());
+        let base64 = path(
+            "base64",
+            "salt=89A6A795C9CCECB5\nkey=Jtbt1ToK+o+hqj+80v/yoL9ICaTgRRH2KfxzLCpCqPw=\niv=Ryo1V63dJSWtTlVXOGNqZw==",
+        );
+        let rotated = path(
+            "rotated",
+            &VALID_KEY_FILE_CONTENTS.replace("472A3557", "472A3558"),
+        );
+
+        assert!(key_files_equal(&original, &original).unwrap());
+        assert!(key_files_equal(&original, &copy).unwrap());
+        assert!(key_files_equal(&original, &base64).unwrap());
+        assert!(!key_files_equal(&original, &rotated).unwrap());
+
+        let missing = dir.path().join("missing");
+        let err = key_files_equal(&original, missing.to_str().unwrap()).unwrap_err();
+        assert!(matches!(err, EnvError::IoError(_)));
+        assert!(!err.to_string().contains("26D6EDD5"));
+    }
+
     #[test]
     fn test_parse_key_file_reordered_fields() {
         let dir = tempdir().unwrap();
@@ -432,6 +1268,24 @@ iv=472A3557ADDD2525AD4E555738636A67
         assert_eq!(&*iv, "472A3557ADDD2525AD4E555738636A67");
     }
 
+    #[test]
+    fn test_parse_key_file_crlf_line_endings() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(
+            &key_file_path,
+            "salt=89A6A795C9CCECB5\r\nkey=26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC\r\niv=472A3557ADDD2525AD4E555738636A67\r\r\n",
+        );
+        let kf = key_file_path.to_str().unwrap();
+
+        let (salt, key, iv) = parse_key_file(kf).unwrap();
+        assert_eq!(&*salt, "89A6A795C9CCECB5");
+        assert!(!key.contains('\r'));
+        assert_eq!(&*iv, "472A3557ADDD2525AD4E555738636A67");
+
+        assert_eq!(&*decrypt(ENCRYPTED_VAR_1, kf).unwrap(), DECRYPTED_VAR_1);
+    }
+
     #[test]
     fn test_get_secure_var_or_plain_text() {
         let dir = tempdir().unwrap();
@@ -467,7 +1321,131 @@ iv=472A3557ADDD2525AD4E555738636A67
         let key_file_path = dir.path().join("key-file");
         write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
         let result = decrypt("+encs+ZZ", key_file_path.to_str().unwrap());
-        assert!(matches!(result, Err(EnvError::DecryptionFailed(_))));
+        assert!(matches!(
+            result,
+            Err(EnvError::DecryptionFailed(DecryptError::InvalidHex))
+        ));
+    }
+
+    #[test]
+    fn test_encrypt_matches_geneos_output() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        assert_eq!(encrypt(DECRYPTED_VAR_1, kf).unwrap(), ENCRYPTED_VAR_1);
+        assert_eq!(encrypt(DECRYPTED_VAR_2, kf).unwrap(), ENCRYPTED_VAR_2);
+    }
+
+    #[test]
+    fn test_encrypt_hex_case_round_trip() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let kf = key_file_path.to_str().unwrap();
+
+        let upper = encrypt_with_case("s3cr3t value", kf, HexCase::Upper).unwrap();
+        let lower = encrypt_with_case("s3cr3t value", kf, HexCase::Lower).unwrap();
+
+        assert!(is_encrypted(&upper));
+        assert!(is_encrypted(&lower));
+        assert!(
+            !strip_prefix(&upper)
+                .unwrap()
+                .chars()
+                .any(|c| c.is_ascii_lowercase())
+        );
+        assert!(
+            !strip_prefix(&lower)
+                .unwrap()
+                .chars()
+                .any(|c| c.is_ascii_uppercase())
+        );
+        assert_eq!(upper.to_lowercase(), lower.to_lowercase());
+
+        assert_eq!(&*decrypt(&upper, kf).unwrap(), "s3cr3t value");
+        assert_eq!(&*decrypt(&lower, kf).unwrap(), "s3cr3t value");
+    }
+
+    #[test]
+    fn test_load_env_file_mixed_values() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let env_path = dir.path().join("app.env");
+        std::fs::write(
+            &env_path,
+            format!(
+                "# credentials\nPLAIN=hello world\n\nSECRET_ONE = {ENCRYPTED_VAR_1}\nSECRET_TWO={ENCRYPTED_VAR_2}\nEMPTY=\n"
+            ),
+        )
+        .unwrap();
+
+        let vars =
+            load_env_file(env_path.to_str().unwrap(), key_file_path.to_str().unwrap()).unwrap();
+
+        assert_eq!(vars.len(), 4);
+        assert_eq!(&*vars["PLAIN"], "hello world");
+        assert_eq!(&*vars["SECRET_ONE"], DECRYPTED_VAR_1);
+        assert_eq!(&*vars["SECRET_TWO"], DECRYPTED_VAR_2);
+        assert_eq!(&*vars["EMPTY"], "");
+    }
+
+    #[test]
+    fn test_load_env_file_invalid_line() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let env_path = dir.path().join("app.env");
+        std::fs::write(&env_path, "GOOD=1\nnot a pair\n").unwrap();
+
+        let err = load_env_file(env_path.to_str().unwrap(), key_file_path.to_str().unwrap())
+            .expect_err("expected error for line without '='");
+        assert!(matches!(err, EnvError::EnvFileFormatError(_)));
+        assert_eq!(
+            err.to_string(),
+            "Env file format error: invalid line 2 in env file"
+        );
+    }
+
+    #[test]
+    #[ignore = "Mutates process env; run explicitly if needed"]
+    fn test_load_env_file_into_env() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        let env_path = dir.path().join("app.env");
+        std::fs::write(&env_path, format!("LOADED_SECRET={ENCRYPTED_VAR_1}\n")).unwrap();
+
+        unsafe {
+            load_env_file_into_env(env_path.to_str().unwrap(), key_file_path.to_str().unwrap())
+                .unwrap();
+        }
+        assert_eq!(std::env::var("LOADED_SECRET").unwrap(), DECRYPTED_VAR_1);
+
+        unsafe {
+            std::env::remove_var("LOADED_SECRET");
+        }
+    }
+
+    #[test]
+    fn test_decrypt_truncated_ciphertext() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        // 15 bytes: one byte short of a full AES block
+        let truncated = &ENCRYPTED_VAR_1[..ENCRYPTED_VAR_1.len() - 2];
+        let result = decrypt(truncated, key_file_path.to_str().unwrap());
+        let err = result.expect_err("expected error for truncated ciphertext");
+        assert!(matches!(
+            err,
+            EnvError::DecryptionFailed(DecryptError::InvalidCiphertextLength)
+        ));
+        assert_eq!(
+            err.to_string(),
+            "decryption failed: ciphertext length not a multiple of block size"
+        );
     }
 
     #[test]
@@ -477,9 +1455,10 @@ iv=472A3557ADDD2525AD4E555738636A67
         write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
         let result = decrypt("+encs+", key_file_path.to_str().unwrap());
         let err = result.expect_err("expected error for empty ciphertext");
-        assert!(matches!(err, EnvError::DecryptionFailed(_)));
-        let msg = format!("{}", err);
-        assert_eq!(msg, "decryption failed", "empty ciphertext must be opaque");
+        assert!(matches!(
+            err,
+            EnvError::DecryptionFailed(DecryptError::InvalidCiphertextLength)
+        ));
     }
 
     #[test]
@@ -487,22 +1466,71 @@ iv=472A3557ADDD2525AD4E555738636A67
         let dir = tempdir().unwrap();
         let key_file_path = dir.path().join("key-file");
         write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
-        // Invalid hex should produce an opaque error, not leak hex library details
+        // Invalid hex must not leak hex library details or the input
         let result = decrypt("+encs+ZZ", key_file_path.to_str().unwrap());
-        if let Err(EnvError::DecryptionFailed(ref inner)) = result {
-            assert_eq!(
-                inner, "decryption failed",
-                "inner message must be opaque, got: {inner}"
-            );
-        } else {
-            panic!("expected DecryptionFailed variant");
-        }
-        // Display must also be opaque
         let msg = format!("{}", result.unwrap_err());
         assert_eq!(
-            msg, "decryption failed",
-            "Display must be opaque, got: {msg}"
+            msg, "decryption failed: ciphertext is not valid hex",
+            "Display must not leak details, got: {msg}"
+        );
+        assert!(!msg.contains("ZZ"));
+    }
+
+    #[test]
+    fn test_decrypt_error_variants() {
+        let dir = tempdir().unwrap();
+        let kf_path = |name: &str, contents: &str| {
+            let path = dir.path().join(name);
+            write_key_file(&path, contents);
+            path.to_str().unwrap().to_string()
+        };
+        let assert_variant =
+            |value: &str, kf: &str, expected: DecryptError| match decrypt(value, kf) {
+                Err(EnvError::DecryptionFailed(e)) => assert_eq!(e, expected),
+                other => panic!("expected {expected:?}, got {:?}", other.map(|_| ())),
+            };
+
+        let valid = kf_path("valid", VALID_KEY_FILE_CONTENTS);
+        assert_variant("+encs+ZZ", &valid, DecryptError::InvalidHex);
+        assert_variant("+encs+ABCD", &valid, DecryptError::InvalidCiphertextLength);
+
+        let bad_key_hex = kf_path(
+            "bad-key-hex",
+            "salt=89A6A795C9CCECB5\nkey=XYZ\niv=472A3557ADDD2525AD4E555738636A67",
+        );
+        assert_variant(ENCRYPTED_VAR_1, &bad_key_hex, DecryptError::InvalidKeyHex);
+
+        let bad_iv_hex = kf_path(
+            "bad-iv-hex",
+            "salt=89A6A795C9CCECB5\nkey=26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC\niv=XYZ",
+        );
+        assert_variant(ENCRYPTED_VAR_1, &bad_iv_hex, DecryptError::InvalidIvHex);
+
+        let short_key = kf_path(
+            "short-key",
+            "salt=89A6A795C9CCECB5\nkey=26D6EDD5\niv=472A3557ADDD2525AD4E555738636A67",
+        );
+        assert_variant(ENCRYPTED_VAR_1, &short_key, DecryptError::BadKeyOrIvLength);
+
+        let wrong_key = kf_path(
+            "wrong-key",
+            "salt=89A6A795C9CCECB5\nkey=AABBCCDDEEFF00112233445566778899AABBCCDDEEFF00112233445566778899\niv=472A3557ADDD2525AD4E555738636A67",
         );
+        assert_variant(ENCRYPTED_VAR_1, &wrong_key, DecryptError::CipherError);
+
+        // Valid padding around bytes that are not UTF-8
+        let not_utf8 = {
+            use cipher::BlockEncryptMut;
+            let key =
+                Vec::from_hex("26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC")
+                    .unwrap();
+            let iv = Vec::from_hex("472A3557ADDD2525AD4E555738636A67").unwrap();
+            let ct = Encryptor::<aes::Aes256>::new_from_slices(&key, &iv)
+                .unwrap()
+                .encrypt_padded_vec_mut::<Pkcs7>(&[0xFF, 0xFE]);
+            format!("+encs+{}", hex::encode_upper(ct))
+        };
+        assert_variant(&not_utf8, &valid, DecryptError::InvalidUtf8);
     }
 
     #[test]
@@ -581,15 +1609,11 @@ iv=472A3557ADDD2525AD4E555738636A67
 
     #[test]
     fn test_env_error_debug_redacts_crypto() {
-        let decryption_err = EnvError::DecryptionFailed("secret stuff".to_string());
-        let debug_str = format!("{:?}", decryption_err);
-        assert!(
-            debug_str.contains("[REDACTED]"),
-            "Debug must redact inner message, got: {debug_str}"
-        );
-        assert!(
-            !debug_str.contains("secret stuff"),
-            "Debug must not expose inner message, got: {debug_str}"
+        // Decryption errors carry only a reason, never key or plaintext data
+        let decryption_err = EnvError::DecryptionFailed(DecryptError::CipherError);
+        assert_eq!(
+            format!("{:?}", decryption_err),
+            "DecryptionFailed(CipherError)"
         );
 
         let key_err = EnvError::KeyFileFormatError("secret stuff".to_string());
@@ -615,4 +1639,73 @@ iv=472A3557ADDD2525AD4E555738636A67
             "error must not leak file path, got: {msg}"
         );
     }
+
+    #[test]
+    fn test_validate_key_file() {
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        let kf = key_file_path.to_str().unwrap();
+
+        assert_eq!(validate_key_file(kf), Err(KeyFileProblem::Unreadable));
+
+        write_key_file(&key_file_path, VALID_KEY_FILE_CONTENTS);
+        assert!(validate_key_file(kf).is_ok());
+
+        write_key_file(
+            &key_file_path,
+            "salt=00\nkey=ABCD\niv=472A3557ADDD2525AD4E555738636A67",
+        );
+        assert_eq!(
+            validate_key_file(kf),
+            Err(KeyFileProblem::InvalidKeyMaterial)
+        );
+
+        write_key_file(&key_file_path, "salt=00\nkey=ZZ\n");
+        assert_eq!(validate_key_file(kf), Err(KeyFileProblem::Malformed));
+    }
+
+    #[test]
+    fn test_key_file_errors_do_not_leak_key_material() {
+        const KEY: &str = "26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC";
+        const IV: &str = "472A3557ADDD2525AD4E555738636A67";
+
+        let broken_files = [
+            format!("salt=00\nkey={KEY}\niv={IV}\nkey={KEY}"),
+            format!("salt=00\nkey={KEY}\n{IV}"),
+            format!("salt=00\nkey={KEY}\nextra={IV}"),
+            format!("salt=00\nkey={KEY}X\niv={IV}"),
+            format!("salt=00\nkey={KEY}\niv={IV}00"),
+            format!("salt=00\nkey={}\niv={IV}", &KEY[..40]),
+            format!("salt=00\nkey={KEY}"),
+        ];
+
+        let dir = tempdir().unwrap();
+        let key_file_path = dir.path().join("key-file");
+        let kf = key_file_path.to_str().unwrap();
+
+        for contents in &broken_files {
+            write_key_file(&key_file_path, contents);
+
+            let mut messages = vec![validate_key_file(kf).unwrap_err().to_string()];
+            for err in [
+                decrypt(ENCRYPTED_VAR_1, kf).unwrap_err(),
+                encrypt("plain", kf).unwrap_err(),
+            ] {
+                messages.push(err.to_string());
+                messages.push(format!("{err:?}"));
+            }
+
+            for message in &messages {
+                for secret in [KEY, IV] {
+                    for window in secret.as_bytes().windows(8) {
+                        let window = std::str::from_utf8(window).unwrap();
+                        assert!(
+                            !message.contains(window),
+                            "error leaks key material: {message}"
+                        );
+                    }
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,136 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// A Geneos severity level.
+///
+/// Severities are ordered from least to most severe:
+/// `Undefined < Ok < Warning < Critical`, so the worst severity of a set can
+/// be found with `Iterator::max`.
+///
+/// # Example
+/// ```
+/// use geneos_toolkit::severity::Severity;
+///
+/// let worst = ["ok", "CRITICAL", "Warning"]
+///     .into_iter()
+///     .map(Severity::from)
+///     .max();
+/// assert_eq!(worst, Some(Severity::Critical));
+/// ```
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum Severity {
+    #[default]
+    Undefined,
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    /// Returns the canonical upper-case name used by Geneos.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Undefined => "UNDEFINED",
+            Severity::Ok => "OK",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error returned when a string is not a recognised severity name.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ParseSeverityError(String);
+
+impl fmt::Display for ParseSeverityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Unknown severity: {}", self.0)
+    }
+}
+
+impl Error for ParseSeverityError {}
+
+impl FromStr for Severity {
+    type Err = ParseSeverityError;
+
+    /// Parses a severity name, ignoring case and surrounding whitespace.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        [
+            Severity::Undefined,
+            Severity::Ok,
+            Severity::Warning,
+            Severity::Critical,
+        ]
+        .into_iter()
+        .find(|sev| sev.as_str().eq_ignore_ascii_case(trimmed))
+        .ok_or_else(|| ParseSeverityError(s.to_string()))
+    }
+}
+
+impl From<&str> for Severity {
+    /// Parses a severity name, falling back to `Undefined` for unknown values.
+    fn from(s: &str) -> Self {
+        s.parse().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_case_insensitive() {
+        assert_eq!("OK".parse::<Severity>().unwrap(), Severity::Ok);
+        assert_eq!("warning".parse::<Severity>().unwrap(), Severity::Warning);
+        assert_eq!(
+            " Critical ".parse::<Severity>().unwrap(),
+            Severity::Critical
+        );
+        assert_eq!(
+            "undefined".parse::<Severity>().unwrap(),
+            Severity::Undefined
+        );
+        assert!("bogus".parse::<Severity>().is_err());
+    }
+
+    #[test]
+    fn test_from_str_falls_back_to_undefined() {
+        assert_eq!(Severity::from("critical"), Severity::Critical);
+        assert_eq!(Severity::from("bogus"), Severity::Undefined);
+        assert_eq!(Severity::from(""), Severity::Undefined);
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(Severity::Undefined < Severity::Ok);
+        assert!(Severity::Ok < Severity::Warning);
+        assert!(Severity::Warning < Severity::Critical);
+        assert_eq!(
+            [Severity::Ok, Severity::Critical, Severity::Warning]
+                .into_iter()
+                .max(),
+            Some(Severity::Critical)
+        );
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        for sev in [
+            Severity::Undefined,
+            Severity::Ok,
+            Severity::Warning,
+            Severity::Critical,
+        ] {
+            assert_eq!(sev.to_string().parse::<Severity>().unwrap(), sev);
+        }
+        assert_eq!(Severity::Warning.to_string(), "WARNING");
+    }
+}
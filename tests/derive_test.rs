@@ -0,0 +1,108 @@
+#![cfg(feature = "derive")]
+
+use geneos_toolkit::dataview::ToRow;
+use geneos_toolkit::prelude::*;
+use pretty_assertions::assert_eq;
+use std::collections::HashMap;
+
+#[derive(ToRow)]
+struct Process {
+    pid: u32,
+    #[torow(name)]
+    name: String,
+    /// Percent of one core.
+    #[torow(rename = "cpu %")]
+    cpu: f64,
+    #[torow(skip)]
+    #[allow(dead_code)]
+    environment: HashMap<String, Vec<String>>,
+    pub(crate) state: &'static str,
+}
+
+#[derive(ToRow)]
+pub struct Disk {
+    pub r#mount: String,
+    pub used: u64,
+}
+
+#[test]
+fn test_derive_to_row_builds_view() {
+    let processes = vec![
+        Process {
+            pid: 101,
+            name: "sshd".to_string(),
+            cpu: 0.5,
+            environment: HashMap::new(),
+            state: "running",
+        },
+        Process {
+            pid: 202,
+            name: "cron, daily".to_string(),
+            cpu: 12.0,
+            environment: HashMap::from([("PATH".to_string(), vec!["/bin".to_string()])]),
+            state: "sleeping",
+        },
+    ];
+
+    let row = processes[0].to_row();
+    assert_eq!(row.name(), "sshd");
+    assert_eq!(row.len(), 3);
+
+    let view = Dataview::builder()
+        .set_row_header("process")
+        .add_rows(&processes)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        view.to_string(),
+        "process,pid,cpu %,state\nsshd,101,0.5,running\ncron\\, daily,202,12,sleeping"
+    );
+}
+
+#[test]
+fn test_derive_to_row_defaults_to_first_field_for_name() {
+    let view = Dataview::builder()
+        .set_row_header("mount")
+        .add_rows(&[Disk {
+            mount: "/".to_string(),
+            used: 42,
+        }])
+        .build()
+        .unwrap();
+
+    assert_eq!(view.to_string(), "mount,used\n/,42");
+}
+
+#[derive(ToRow)]
+struct Gauge<T>
+where
+    T: std::fmt::Display,
+{
+    #[torow(skip)]
+    #[allow(dead_code)]
+    id: u64,
+    name: &'static str,
+    value: T,
+}
+
+#[test]
+fn test_derive_to_row_supports_generics_and_skipped_first_field() {
+    let view = Dataview::builder()
+        .set_row_header("gauge")
+        .add_rows(&[Gauge {
+            id: 7,
+            name: "load",
+            value: 0.75,
+        }])
+        .build()
+        .unwrap();
+
+    assert_eq!(view.to_string(), "gauge,value\nload,0.75");
+}
+
+#[test]
+fn test_derive_to_row_rejects_invalid_input() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
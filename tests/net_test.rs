@@ -0,0 +1,115 @@
+#![cfg(feature = "net")]
+
+use geneos_toolkit::dataview::{
+    LineEnding, RenderOptions, TCP_END_MARKER, TCP_END_MARKER_CRLF, send_tcp,
+};
+use geneos_toolkit::prelude::*;
+use pretty_assertions::assert_eq;
+use std::io::Read;
+use std::net::TcpListener;
+use std::thread;
+
+#[test]
+fn test_send_tcp_to_local_listener() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let collector = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accept connection");
+        let mut received = String::new();
+        stream.read_to_string(&mut received).expect("read stream");
+        received
+    });
+
+    let view = Dataview::builder()
+        .set_row_header("host")
+        .add_headline("source", "net_test")
+        .add_value("alpha", "status", "up, running")
+        .add_value("beta", "status", "down")
+        .build()
+        .unwrap();
+
+    send_tcp(&view, &addr).expect("send dataview");
+
+    let received = collector.join().unwrap();
+    assert_eq!(received, format!("{view}{TCP_END_MARKER}"));
+    assert_eq!(
+        received,
+        "\
+host,status
+<!>source,net_test
+alpha,up\\, running
+beta,down
+.
+"
+    );
+}
+
+#[test]
+fn test_send_tcp_crlf_end_marker() {
+    let listener = TcpListener::bind("127.0.0.1:0").expect("bind listener");
+    let addr = listener.local_addr().unwrap().to_string();
+
+    let collector = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("accept connection");
+        let mut received = String::new();
+        stream.read_to_string(&mut received).expect("read stream");
+        received
+    });
+
+    let view = Dataview::builder()
+        .set_row_header("host")
+        .render_options(RenderOptions {
+            line_ending: LineEnding::Crlf,
+            ..RenderOptions::default()
+        })
+        .add_value("alpha", "status", "up")
+        .build()
+        .unwrap();
+
+    send_tcp(&view, &addr).expect("send dataview");
+
+    let received = collector.join().unwrap();
+    assert_eq!(received, format!("{view}{TCP_END_MARKER_CRLF}"));
+    assert_eq!(received, "host,status\r\nalpha,up\r\n.\r\n");
+}
+
+#[test]
+fn test_send_tcp_rejects_dot_line() {
+    // Nothing listens here, so an attempt to connect would fail differently
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    };
+
+    let view = Dataview::builder()
+        .set_row_header("host")
+        .render_options(RenderOptions {
+            hide_empty_columns: true,
+            ..RenderOptions::default()
+        })
+        .add_value(".", "status", "")
+        .build()
+        .unwrap();
+    assert_eq!(view.to_string(), "host\n.");
+
+    let err = send_tcp(&view, &addr).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn test_send_tcp_connection_refused() {
+    // Bind then drop to obtain a local port with nothing listening
+    let addr = {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().to_string()
+    };
+
+    let view = Dataview::builder()
+        .set_row_header("id")
+        .add_value("r1", "c1", "v1")
+        .build()
+        .unwrap();
+
+    assert!(send_tcp(&view, &addr).is_err());
+}
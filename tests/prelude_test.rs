@@ -0,0 +1,34 @@
+#![cfg(feature = "secure-env")]
+
+use geneos_toolkit::prelude::*;
+use std::fs;
+use tempfile::tempdir;
+
+const KEY_FILE_CONTENTS: &str = "\
+salt=89A6A795C9CCECB5
+key=26D6EDD53A0AFA8FA1AA3FBCD2FFF2A0BF4809A4E04511F629FC732C2A42A8FC
+iv=472A3557ADDD2525AD4E555738636A67
+";
+
+#[test]
+fn test_prelude_decrypt_is_canonical_path() {
+    let dir = tempdir().unwrap();
+    let key_file_path = dir.path().join("key-file");
+    fs::write(&key_file_path, KEY_FILE_CONTENTS).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&key_file_path, fs::Permissions::from_mode(0o600)).unwrap();
+    }
+    let kf = key_file_path.to_str().unwrap();
+
+    let encrypted = "+encs+BCC9E963342C9CFEFB45093F3437A680";
+    assert!(is_encrypted(encrypted));
+
+    let secret: Zeroizing<String> = decrypt(encrypted, kf).unwrap();
+    assert_eq!(&*secret, "12345");
+    assert_eq!(
+        &*decrypt(encrypted, kf).unwrap(),
+        &*geneos_toolkit::secure_env::decrypt(encrypted, kf).unwrap()
+    );
+}
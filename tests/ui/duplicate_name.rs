@@ -0,0 +1,11 @@
+use geneos_toolkit::dataview::ToRow;
+
+#[derive(ToRow)]
+struct Process {
+    #[torow(name)]
+    pid: u32,
+    #[torow(name)]
+    name: String,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use geneos_toolkit::dataview::ToRow;
+
+#[derive(ToRow)]
+enum State {
+    Running,
+    Stopped,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use geneos_toolkit::dataview::ToRow;
+
+#[derive(ToRow)]
+struct Process {
+    name: String,
+    #[torow(rename = "")]
+    pid: u32,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use geneos_toolkit::dataview::ToRow;
+
+#[derive(ToRow)]
+struct Process {
+    #[torow(name, rename = "process")]
+    name: String,
+    pid: u32,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use geneos_toolkit::dataview::ToRow;
+
+#[derive(ToRow)]
+struct Process {
+    #[torow(skip)]
+    name: String,
+}
+
+fn main() {}
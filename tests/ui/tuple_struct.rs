@@ -0,0 +1,6 @@
+use geneos_toolkit::dataview::ToRow;
+
+#[derive(ToRow)]
+struct Process(u32, String);
+
+fn main() {}